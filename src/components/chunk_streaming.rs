@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+/// How much of the world is kept loaded around the player, in chunks.
+///
+/// Distances are counted in whole chunks along either axis, so a radius of 2 keeps a 5x5
+/// block loaded. Chunks only unload past `unload_radius`, which keeps a player walking along
+/// a chunk border from loading and unloading the same chunks over and over.
+#[derive(Resource, Clone, Debug)]
+pub struct ChunkStreamingConfig {
+    pub load_radius: i32,
+    pub unload_radius: i32,
+    pub max_loads_per_tick: usize, // Spreads the cost of crossing into a new chunk over ticks
+}
+
+impl Default for ChunkStreamingConfig {
+    fn default() -> Self {
+        Self { load_radius: 2, unload_radius: 3, max_loads_per_tick: 2 }
+    }
+}
+
+/// Chunks spawned or despawned by streaming on this tick.
+#[derive(Message, Default)]
+pub struct ChunksStreamedEvent {
+    pub loaded: Vec<(i32, i32)>,
+    pub unloaded: Vec<(i32, i32)>,
+}
+
+impl ChunkStreamingConfig {
+    /// Chunks within `radius` of `center`, nearest first.
+    pub fn chunks_around(center: (i32, i32), radius: i32) -> Vec<(i32, i32)> {
+        let mut chunks: Vec<(i32, i32)> = (-radius..=radius)
+            .flat_map(|dz| (-radius..=radius).map(move |dx| (center.0 + dx, center.1 + dz)))
+            .collect();
+        chunks.sort_by_key(|&(x, z)| ((x - center.0).pow(2) + (z - center.1).pow(2), z, x));
+        chunks
+    }
+
+    /// Chunks to spawn and to despawn for a player standing in `center`, given the chunks
+    /// loaded now. Loads are capped at `max_loads_per_tick`, nearest first.
+    pub fn plan(
+        &self,
+        center: (i32, i32),
+        loaded: impl Iterator<Item = (i32, i32)>,
+        is_loaded: impl Fn((i32, i32)) -> bool,
+    ) -> ChunksStreamedEvent {
+        let distance = |(x, z): (i32, i32)| (x - center.0).abs().max((z - center.1).abs());
+        let mut unloaded: Vec<(i32, i32)> =
+            loaded.filter(|&chunk| distance(chunk) > self.unload_radius).collect();
+        unloaded.sort();
+        let loaded = Self::chunks_around(center, self.load_radius)
+            .into_iter()
+            .filter(|&chunk| !is_loaded(chunk))
+            .take(self.max_loads_per_tick)
+            .collect();
+        ChunksStreamedEvent { loaded, unloaded }
+    }
+}
+
+impl ChunksStreamedEvent {
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty() && self.unloaded.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn loads_nearest_chunks_first_and_unloads_with_hysteresis() {
+        let config =
+            ChunkStreamingConfig { load_radius: 1, unload_radius: 2, max_loads_per_tick: 3 };
+        let loaded: HashSet<(i32, i32)> = [(0, 0), (1, 0), (-2, 0), (3, 0)].into();
+        let plan = config.plan((0, 0), loaded.iter().copied(), |chunk| loaded.contains(&chunk));
+
+        // Orthogonal neighbours come before diagonal ones
+        assert_eq!(plan.loaded, vec![(0, -1), (-1, 0), (0, 1)]);
+        // (-2, 0) is outside the load radius but still inside the unload radius
+        assert_eq!(plan.unloaded, vec![(3, 0)]);
+        assert_eq!(ChunkStreamingConfig::chunks_around((5, 5), 2).len(), 25);
+    }
+}
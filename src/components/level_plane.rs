@@ -1,5 +1,9 @@
 use bevy::prelude::*;
 
+/// Decoration stamped into the level by a prefab.
+#[derive(Component)]
+pub struct LevelProp;
+
 #[derive(Component, Clone, Copy)]
 pub struct LevelPlane {
     pub chunk_num_x: i32,
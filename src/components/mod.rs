@@ -1,9 +1,18 @@
 pub mod camera;
+pub mod chunk_streaming;
 pub mod level_plane;
 pub mod movements;
 pub mod plane_chunk;
+pub mod simulation;
+pub mod terrain_type;
+pub mod tile_grid;
+pub mod tile_highlight;
 pub mod animation;
 
 pub use camera::*;
 pub use plane_chunk::*;
+pub use simulation::*;
+pub use terrain_type::*;
+pub use tile_grid::*;
+pub use tile_highlight::*;
 pub use animation::*;
\ No newline at end of file
@@ -1,30 +1,29 @@
 use std::cmp::Ordering;
 
-use bevy::prelude::*;
-
+/// Open-set entry of the tile searches; the heap pops the lowest `f_score` first.
 #[derive(Clone)]
-pub struct AStarNode {
-    pub entity: Entity,
+pub struct AStarNode<N> {
+    pub node: N,
     pub f_score: f32,
     pub g_score: f32,
 }
 
-impl PartialEq for AStarNode {
+impl<N> PartialEq for AStarNode<N> {
     fn eq(&self, other: &Self) -> bool {
         self.f_score == other.f_score
     }
 }
 
-impl Eq for AStarNode {}
+impl<N> Eq for AStarNode<N> {}
 
-impl PartialOrd for AStarNode {
+impl<N> PartialOrd for AStarNode<N> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.f_score.partial_cmp(&self.f_score)
+        Some(self.cmp(other))
     }
 }
 
-impl Ord for AStarNode {
+impl<N> Ord for AStarNode<N> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+        other.f_score.partial_cmp(&self.f_score).unwrap_or(Ordering::Equal)
     }
 }
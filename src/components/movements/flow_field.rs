@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::components::TileGrid;
+
+const UNREACHED: u32 = u32::MAX;
+
+/// Result of a Dijkstra run, in flat arrays indexed by `TileGraph::index`.
+#[derive(Clone, Default)]
+pub struct ShortestPaths {
+    pub distances: Vec<f32>,
+    pub came_from: Vec<u32>,
+    /// Number of tiles reached, the source included
+    pub reached: usize,
+}
+
+impl ShortestPaths {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            distances: vec![f32::INFINITY; capacity],
+            came_from: vec![UNREACHED; capacity],
+            reached: 0,
+        }
+    }
+
+    pub fn distance(&self, index: usize) -> Option<f32> {
+        self.distances.get(index).copied().filter(|distance| distance.is_finite())
+    }
+
+    pub fn came_from(&self, index: usize) -> Option<usize> {
+        self.came_from
+            .get(index)
+            .copied()
+            .filter(|&from| from != UNREACHED)
+            .map(|from| from as usize)
+    }
+}
+
+/// Shared Dijkstra integration field towards a single target tile.
+///
+/// Every reachable tile points at the neighbour that is one step closer to the target,
+/// so any number of chasers can follow it without running their own search.
+#[derive(Resource, Default)]
+pub struct FlowField {
+    pub target_tile: Option<Entity>,
+    /// Grid the field was built on; `paths` is indexed by its tile indices
+    pub tiles: Arc<TileGrid>,
+    pub paths: ShortestPaths,
+}
+
+impl FlowField {
+    pub fn next_tile(&self, from: Entity) -> Option<Entity> {
+        let index = self.tiles.index_of_entity(from)?;
+        let next = self.paths.came_from(index as usize)?;
+        self.tiles.entity(next as u32)
+    }
+
+    pub fn distance(&self, from: Entity) -> Option<f32> {
+        self.paths.distance(self.tiles.index_of_entity(from)? as usize)
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::components::movements::hierarchical::TileCoord;
+use crate::components::movements::tile_graph::TileGraph;
+use crate::components::NEIGHBOR_DIRECTIONS;
+#[cfg(test)]
+use crate::components::TerrainType;
+#[cfg(test)]
+use crate::level::tile_types::TileTypes;
+
+/// Plain in-memory tile grid, independent of the ECS.
+///
+/// Useful for tools and tests; coordinates run from `origin` to
+/// `origin + (width - 1, height - 1)`.
+#[derive(Clone, Debug, Default)]
+pub struct GridMap {
+    pub origin: TileCoord,
+    pub width: i32,
+    pub height: i32,
+    pub tile_size: f32,
+    pub walkable: Vec<bool>,
+    pub movement_costs: Vec<f32>,
+    /// Height of each tile centre, in the same units as `tile_size`
+    pub elevations: Vec<f32>,
+    /// Named tiles, e.g. the `S` and `G` of an ASCII map
+    pub markers: HashMap<char, TileCoord>,
+}
+
+impl GridMap {
+    /// Open ground everywhere.
+    pub fn new(width: i32, height: i32, tile_size: f32) -> Self {
+        let len = (width.max(0) * height.max(0)) as usize;
+        Self {
+            origin: (0, 0),
+            width,
+            height,
+            tile_size,
+            walkable: vec![true; len],
+            movement_costs: vec![1.0; len],
+            elevations: vec![0.0; len],
+            markers: HashMap::new(),
+        }
+    }
+
+    /// Unit-sized tiles covering `min..=max`, all blocked until filled in.
+    pub fn spanning(min: TileCoord, max: TileCoord) -> Self {
+        let mut grid = Self::new(max.0 - min.0 + 1, max.1 - min.1 + 1, 1.0);
+        grid.origin = min;
+        grid.walkable.fill(false);
+        grid
+    }
+
+    /// Parses an ASCII-art map, one row per line with `z` growing downwards.
+    ///
+    /// `#` is blocked, `.` ground, `=` asphalt, `:` sand, `%` rubble, `~` shallow water and
+    /// `&` toxic sludge, at the movement costs of the shipped tile types. Letters mark ground
+    /// tiles that can be looked up in `markers`.
+    /// Leading and trailing whitespace on each line is ignored, as are blank lines.
+    ///
+    /// Panics on any other character.
+    #[cfg(test)]
+    pub fn from_ascii(map: &str) -> Self {
+        let tile_types: TileTypes =
+            ron::de::from_str(include_str!("../../../assets/tiles/wasteland.tiles.ron")).unwrap();
+        let rows: Vec<&str> = map.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0) as i32;
+        let mut grid = Self::new(width, rows.len() as i32, 1.0);
+
+        for (z, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.chars().enumerate() {
+                let coord = (x as i32, z as i32);
+                let terrain = match symbol {
+                    '#' => {
+                        grid.set_walkable(coord, false);
+                        continue;
+                    }
+                    '.' => "Ground",
+                    '=' => "Asphalt",
+                    ':' => "Sand",
+                    '%' => "Rubble",
+                    '~' => "ShallowWater",
+                    '&' => "ToxicSludge",
+                    marker if marker.is_ascii_alphabetic() => {
+                        grid.markers.insert(marker, coord);
+                        "Ground"
+                    }
+                    other => panic!("Unknown map symbol {other:?} at ({x}, {z})"),
+                };
+                let movement_cost = tile_types.types[&TerrainType::named(terrain)].movement_cost;
+                grid.set_movement_cost(coord, movement_cost);
+            }
+            // Short rows are padded with blocked tiles
+            for x in row.chars().count() as i32..width {
+                grid.set_walkable((x, z as i32), false);
+            }
+        }
+        grid
+    }
+
+    /// Moves the map to cover `min..=max`, keeping the tiles it already had there. Tiles new
+    /// to the map are blocked, as in `spanning`.
+    pub fn resize(&mut self, min: TileCoord, max: TileCoord) {
+        let mut resized = Self::spanning(min, max);
+        if (resized.origin, resized.width, resized.height) == (self.origin, self.width, self.height)
+        {
+            return;
+        }
+        resized.tile_size = self.tile_size;
+        for index in 0..self.node_capacity() {
+            let coord = self.node_at_index(index);
+            if let Some(new_index) = resized.offset(coord) {
+                resized.walkable[new_index] = self.walkable[index];
+                resized.movement_costs[new_index] = self.movement_costs[index];
+                resized.elevations[new_index] = self.elevations[index];
+            }
+        }
+        resized.markers = std::mem::take(&mut self.markers);
+        *self = resized;
+    }
+
+    fn offset(&self, (x, z): TileCoord) -> Option<usize> {
+        let (x, z) = (x - self.origin.0, z - self.origin.1);
+        ((0..self.width).contains(&x) && (0..self.height).contains(&z))
+            .then(|| (z * self.width + x) as usize)
+    }
+
+    pub fn contains(&self, coord: TileCoord) -> bool {
+        self.offset(coord).is_some()
+    }
+
+    #[cfg(test)]
+    pub fn marker(&self, marker: char) -> Option<TileCoord> {
+        self.markers.get(&marker).copied()
+    }
+
+    pub fn set_walkable(&mut self, coord: TileCoord, walkable: bool) {
+        if let Some(index) = self.offset(coord) {
+            self.walkable[index] = walkable;
+        }
+    }
+
+    pub fn set_movement_cost(&mut self, coord: TileCoord, movement_cost: f32) {
+        if let Some(index) = self.offset(coord) {
+            self.movement_costs[index] = movement_cost;
+        }
+    }
+
+    pub fn set_elevation(&mut self, coord: TileCoord, elevation: f32) {
+        if let Some(index) = self.offset(coord) {
+            self.elevations[index] = elevation;
+        }
+    }
+}
+
+impl TileGraph for GridMap {
+    type Node = TileCoord;
+
+    fn node_capacity(&self) -> usize {
+        self.walkable.len()
+    }
+
+    fn index(&self, node: TileCoord) -> usize {
+        self.offset(node).expect("tile outside the map")
+    }
+
+    fn node_at_index(&self, index: usize) -> TileCoord {
+        let index = index as i32;
+        (self.origin.0 + index % self.width, self.origin.1 + index / self.width)
+    }
+
+    fn node_at(&self, coord: TileCoord) -> Option<TileCoord> {
+        self.contains(coord).then_some(coord)
+    }
+
+    fn coord(&self, node: TileCoord) -> TileCoord {
+        node
+    }
+
+    fn position(&self, (x, z): TileCoord) -> Vec3 {
+        let elevation = self.offset((x, z)).map_or(0.0, |index| self.elevations[index]);
+        Vec3::new(x as f32 * self.tile_size, elevation, z as f32 * self.tile_size)
+    }
+
+    fn is_walkable(&self, node: TileCoord) -> bool {
+        self.offset(node).is_some_and(|index| self.walkable[index])
+    }
+
+    fn movement_cost(&self, node: TileCoord) -> f32 {
+        self.offset(node).map_or(1.0, |index| self.movement_costs[index])
+    }
+
+    fn neighbors(&self, (x, z): TileCoord) -> impl Iterator<Item = TileCoord> {
+        NEIGHBOR_DIRECTIONS
+            .into_iter()
+            .map(move |(dx, dz)| (x + dx, z + dz))
+            .filter(move |&neighbor| self.can_step((x, z), neighbor))
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::components::TerrainType;
+use crate::components::movements::grid_map::GridMap;
+use crate::components::movements::tile_graph::TileGraph;
+
+pub type TileCoord = (i32, i32);
+pub type ClusterCoord = (i32, i32);
+
+/// Abstract edges between the entrances of a single cluster (one `PlaneChunk`).
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Cluster {
+    /// Entrance -> (other entrance, cost of the shortest path inside the cluster)
+    pub edges: HashMap<TileCoord, Vec<(TileCoord, f32)>>,
+}
+
+/// HPA* abstraction of the tile grid, using each `PlaneChunk` as a cluster.
+///
+/// Entrances are pairs of walkable tiles facing each other across a chunk border, or
+/// diagonally across the corner where four chunks meet.
+/// Costs are in tile units (1 for straight steps, sqrt(2) for diagonals) weighted by
+/// each tile's movement cost and the climb, with elevations scaled to tile units too.
+#[derive(Resource, Clone, Default)]
+pub struct ClusterGraph {
+    pub cluster_size: i32,
+    /// Walkability and movement cost of every tile, in tile units
+    pub tiles: GridMap,
+    /// Entrance pairs keyed by the two clusters they connect, lower cluster first.
+    pub borders: HashMap<(ClusterCoord, ClusterCoord), Vec<(TileCoord, TileCoord)>>,
+    pub clusters: HashMap<ClusterCoord, Cluster>,
+}
+
+impl ClusterGraph {
+    pub fn cluster_of(&self, tile: TileCoord) -> ClusterCoord {
+        (tile.0.div_euclid(self.cluster_size), tile.1.div_euclid(self.cluster_size))
+    }
+
+    pub fn is_walkable(&self, tile: TileCoord) -> bool {
+        self.tiles.is_walkable(tile)
+    }
+
+    pub fn movement_cost(&self, tile: TileCoord) -> f32 {
+        self.tiles.movement_cost(tile)
+    }
+
+    /// Keys of the (up to eight) borders a cluster shares with its neighbours, the four
+    /// sides first and then the four corners. Corner keys put the southern cluster first.
+    pub fn border_keys(cluster: ClusterCoord) -> [(ClusterCoord, ClusterCoord); 8] {
+        let (cx, cz) = cluster;
+        [
+            ((cx - 1, cz), cluster),
+            (cluster, (cx + 1, cz)),
+            ((cx, cz - 1), cluster),
+            (cluster, (cx, cz + 1)),
+            ((cx - 1, cz - 1), cluster),
+            ((cx + 1, cz - 1), cluster),
+            (cluster, (cx - 1, cz + 1)),
+            (cluster, (cx + 1, cz + 1)),
+        ]
+    }
+
+    /// All entrance tiles lying inside `cluster`.
+    pub fn entrances(&self, cluster: ClusterCoord) -> Vec<TileCoord> {
+        let mut entrances = Vec::new();
+        for key in Self::border_keys(cluster) {
+            if let Some(pairs) = self.borders.get(&key) {
+                for &(low, high) in pairs {
+                    let inside = if key.0 == cluster { low } else { high };
+                    if !entrances.contains(&inside) {
+                        entrances.push(inside);
+                    }
+                }
+            }
+        }
+        entrances
+    }
+
+    /// Entrance tiles directly across a border from `tile`.
+    pub fn inter_edges(&self, tile: TileCoord) -> Vec<TileCoord> {
+        let cluster = self.cluster_of(tile);
+        let mut edges = Vec::new();
+        for key in Self::border_keys(cluster) {
+            if let Some(pairs) = self.borders.get(&key) {
+                for &(low, high) in pairs {
+                    if low == tile {
+                        edges.push(high);
+                    } else if high == tile {
+                        edges.push(low);
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// The cluster graph's own copy of the grid, in tile units.
+impl TileGraph for ClusterGraph {
+    type Node = TileCoord;
+
+    fn node_capacity(&self) -> usize {
+        self.tiles.node_capacity()
+    }
+
+    fn index(&self, node: TileCoord) -> usize {
+        self.tiles.index(node)
+    }
+
+    fn node_at_index(&self, index: usize) -> TileCoord {
+        self.tiles.node_at_index(index)
+    }
+
+    fn node_at(&self, coord: TileCoord) -> Option<TileCoord> {
+        self.tiles.node_at(coord)
+    }
+
+    fn coord(&self, node: TileCoord) -> TileCoord {
+        node
+    }
+
+    fn position(&self, node: TileCoord) -> Vec3 {
+        self.tiles.position(node)
+    }
+
+    fn is_walkable(&self, node: TileCoord) -> bool {
+        self.tiles.is_walkable(node)
+    }
+
+    fn movement_cost(&self, node: TileCoord) -> f32 {
+        self.tiles.movement_cost(node)
+    }
+
+    fn neighbors(&self, node: TileCoord) -> impl Iterator<Item = TileCoord> {
+        self.tiles.neighbors(node)
+    }
+
+    /// Octile distance, tighter than the straight line on an 8-connected grid.
+    fn heuristic(&self, a: TileCoord, b: TileCoord) -> f32 {
+        let dx = (a.0 - b.0).abs() as f32;
+        let dz = (a.1 - b.1).abs() as f32;
+        (dx.max(dz) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dz))
+            * TerrainType::MIN_MOVEMENT_COST
+    }
+}
+
+/// A single cluster of a `ClusterGraph`; searches on it never leave the cluster.
+pub struct ClusterView<'a> {
+    pub graph: &'a ClusterGraph,
+    pub cluster: ClusterCoord,
+}
+
+impl<'a> ClusterView<'a> {
+    pub fn new(graph: &'a ClusterGraph, cluster: ClusterCoord) -> Self {
+        Self { graph, cluster }
+    }
+
+    fn inside(&self, tile: TileCoord) -> bool {
+        self.graph.cluster_of(tile) == self.cluster
+    }
+
+    fn cluster_origin(&self) -> TileCoord {
+        let size = self.graph.cluster_size;
+        (self.cluster.0 * size, self.cluster.1 * size)
+    }
+}
+
+impl TileGraph for ClusterView<'_> {
+    type Node = TileCoord;
+
+    fn node_capacity(&self) -> usize {
+        (self.graph.cluster_size * self.graph.cluster_size) as usize
+    }
+
+    fn index(&self, (x, z): TileCoord) -> usize {
+        let (origin_x, origin_z) = self.cluster_origin();
+        ((z - origin_z) * self.graph.cluster_size + (x - origin_x)) as usize
+    }
+
+    fn node_at_index(&self, index: usize) -> TileCoord {
+        let (origin_x, origin_z) = self.cluster_origin();
+        let index = index as i32;
+        let size = self.graph.cluster_size;
+        (origin_x + index % size, origin_z + index / size)
+    }
+
+    fn node_at(&self, coord: TileCoord) -> Option<TileCoord> {
+        self.graph.node_at(coord).filter(|&tile| self.inside(tile))
+    }
+
+    fn coord(&self, node: TileCoord) -> TileCoord {
+        node
+    }
+
+    fn position(&self, node: TileCoord) -> Vec3 {
+        self.graph.position(node)
+    }
+
+    fn is_walkable(&self, node: TileCoord) -> bool {
+        self.inside(node) && self.graph.is_walkable(node)
+    }
+
+    fn movement_cost(&self, node: TileCoord) -> f32 {
+        self.graph.movement_cost(node)
+    }
+
+    fn neighbors(&self, node: TileCoord) -> impl Iterator<Item = TileCoord> {
+        TileGraph::neighbors(self.graph, node).filter(|&neighbor| self.inside(neighbor))
+    }
+
+    fn heuristic(&self, from: TileCoord, to: TileCoord) -> f32 {
+        self.graph.heuristic(from, to)
+    }
+}
@@ -1,2 +1,12 @@
 pub mod a_star_movement;
+pub mod flow_field;
+pub mod grid_map;
+pub mod hierarchical;
 pub mod movement;
+pub mod path_request;
+pub mod reservation;
+pub mod search_scratch;
+pub mod steering;
+pub mod tile_graph;
+pub mod tile_graph_snapshot;
+pub mod waypoints;
@@ -1,22 +1,47 @@
+use crate::components::MovementState;
 use bevy::prelude::*;
 use std::collections::VecDeque;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum MovementType {
     ASTAR,
     SHORTEST,
+    HPASTAR,
+    JPS, // Jump Point Search, for large open maps
 }
+
+impl MovementType {
+    /// The next type in the order F3 cycles through them.
+    pub fn next(self) -> Self {
+        match self {
+            MovementType::ASTAR => MovementType::HPASTAR,
+            MovementType::HPASTAR => MovementType::JPS,
+            MovementType::JPS => MovementType::SHORTEST,
+            MovementType::SHORTEST => MovementType::ASTAR,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Movement {
     pub target_transform: Option<Transform>, // Current movement target
     pub path: VecDeque<Entity>,
     pub segment_start: Vec3,
+    pub segment_end: Vec3, // Where the curve around the target tile ends; the tile centre on arrival
     pub translation_progress: f32,
     pub segment_distance: f32,
+    pub wait_time: f32,     // Time spent waiting for the next tile to be released
+    pub current_speed: f32, // Actual speed along the path, carried from segment to segment
+    pub movement_type: Option<MovementType>, // Search that planned the current path, if any
 }
 
 #[derive(Component)]
 pub struct MovementSpeed {
-    pub speed: f32,
+    pub speed: f32,        // Top speed on ground
+    pub turn_rate: f32,    // Radians per second
+    pub acceleration: f32, // Units per second squared
+    pub deceleration: f32, // Units per second squared; sets the braking distance
+    pub run_multiplier: f32, // Top speed multiplier while running
 }
 
 #[derive(Message)]
@@ -27,26 +52,120 @@ pub struct MoveRequestEvent {
     pub target_tile_entity: Entity,
 }
 
+/// Sent when a path request fails, including replans after the map changed under a path.
+#[derive(Message)]
+pub struct PathNotFoundEvent {
+    pub entity: Entity,
+    pub target_tile_entity: Entity,
+}
+
 impl Default for Movement {
     fn default() -> Self {
         Self {
             target_transform: None,
             path: VecDeque::new(),
             segment_start: Vec3::ZERO,
+            segment_end: Vec3::ZERO,
             translation_progress: 0.0,
             segment_distance: 0.0,
+            wait_time: 0.0,
+            current_speed: 0.0,
+            movement_type: None,
         }
     }
 }
 
 impl Default for MovementSpeed {
     fn default() -> Self {
-        Self { speed: 10.0 }
+        Self {
+            speed: 10.0,
+            turn_rate: 10.0,
+            acceleration: 30.0,
+            deceleration: 25.0,
+            run_multiplier: 1.8,
+        }
     }
 }
 
 impl MovementSpeed {
     pub fn enemy() -> Self {
-        Self { speed: 5.0 }
+        Self {
+            speed: 5.0,
+            turn_rate: 6.0,
+            acceleration: 15.0,
+            deceleration: 15.0,
+            run_multiplier: 1.0,
+        }
+    }
+
+    /// Top speed on ground in the given state.
+    pub fn top_speed(&self, movement_state: &MovementState) -> f32 {
+        match movement_state {
+            MovementState::Running => self.speed * self.run_multiplier,
+            _ => self.speed,
+        }
+    }
+
+    /// Distance needed to come to a stop from `current_speed`.
+    pub fn braking_distance(&self, current_speed: f32) -> f32 {
+        current_speed * current_speed / (2.0 * self.deceleration)
+    }
+
+    /// Speed after `delta` seconds: accelerates towards `top_speed`, and brakes once
+    /// `remaining_distance` to the destination gets within braking distance.
+    pub fn next_speed(
+        &self,
+        current_speed: f32,
+        top_speed: f32,
+        remaining_distance: f32,
+        delta: f32,
+    ) -> f32 {
+        // Fastest speed that can still stop in time, kept above a crawl for the final approach
+        let stopping_speed =
+            (2.0 * self.deceleration * remaining_distance).sqrt().max(MIN_ARRIVAL_SPEED);
+        let wanted_speed = top_speed.min(stopping_speed);
+        if current_speed < wanted_speed {
+            (current_speed + self.acceleration * delta).min(wanted_speed)
+        } else {
+            // Terrain slows down gradually, but the stop itself follows the braking curve
+            (current_speed - self.deceleration * delta).max(wanted_speed).min(stopping_speed)
+        }
+    }
+}
+
+/// Lowest speed while braking, so the last stretch isn't crept along
+const MIN_ARRIVAL_SPEED: f32 = 0.5;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accelerates_up_to_top_speed() {
+        let speed = MovementSpeed::default();
+        let mut current_speed = 0.0;
+        let mut elapsed = 0.0;
+        while current_speed < speed.speed {
+            current_speed = speed.next_speed(current_speed, speed.speed, 100.0, 0.01);
+            elapsed += 0.01;
+        }
+        assert_eq!(current_speed, speed.speed);
+        assert!((elapsed - speed.speed / speed.acceleration).abs() < 0.02);
+    }
+
+    #[test]
+    fn stops_within_braking_distance() {
+        let speed = MovementSpeed::default();
+        let mut current_speed = speed.speed;
+        let mut remaining_distance = speed.braking_distance(current_speed);
+        for _ in 0..1000 {
+            if remaining_distance <= 0.0 {
+                break;
+            }
+            current_speed = speed.next_speed(current_speed, speed.speed, remaining_distance, 0.01);
+            remaining_distance -= current_speed * 0.01;
+        }
+        assert!(remaining_distance <= 0.0);
+        assert!(current_speed <= MIN_ARRIVAL_SPEED + speed.deceleration * 0.01);
     }
 }
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::tasks::Task;
+
+use crate::components::movements::movement::MovementType;
+
+/// A path request waiting for a free slot in the per-tick budget.
+pub struct PathRequest {
+    pub entity: Entity,
+    pub movement_type: MovementType,
+    pub target_tile_entity: Entity,
+}
+
+#[derive(Resource, Default)]
+pub struct PathRequestQueue {
+    pub requests: VecDeque<PathRequest>,
+}
+
+#[derive(Resource)]
+pub struct PathfindingConfig {
+    /// Search used for the player's clicks and waypoints; F3 cycles through them
+    pub player_movement_type: MovementType,
+    pub max_requests_per_tick: usize,
+    /// Ticks between starting a search and applying its result. Results are held back until
+    /// then, so a path lands on the same tick however fast the machine is
+    pub result_delay_ticks: u64,
+}
+
+/// Pathfinding running on the `AsyncComputeTaskPool` for the entity it is attached to.
+///
+/// Replacing or removing the component drops the task, which cancels it.
+#[derive(Component)]
+pub struct PathTask {
+    pub target_tile_entity: Entity,
+    pub movement_type: MovementType,
+    pub task: Task<Option<Vec<Entity>>>,
+    /// Simulation tick on which the result is applied
+    pub ready_tick: u64,
+}
+
+impl PathRequestQueue {
+    /// Queues a request, dropping any older one still waiting for the same entity.
+    pub fn push(&mut self, request: PathRequest) {
+        self.requests.retain(|queued| queued.entity != request.entity);
+        self.requests.push_back(request);
+    }
+
+    pub fn pending_target(&self, entity: Entity) -> Option<Entity> {
+        self.requests
+            .iter()
+            .find(|request| request.entity == entity)
+            .map(|request| request.target_tile_entity)
+    }
+}
+
+impl Default for PathfindingConfig {
+    fn default() -> Self {
+        Self {
+            player_movement_type: MovementType::ASTAR,
+            max_requests_per_tick: 4,
+            result_delay_ticks: 3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw_u32(index).unwrap()
+    }
+
+    fn request(character: Entity, target: Entity) -> PathRequest {
+        PathRequest {
+            entity: character,
+            movement_type: MovementType::ASTAR,
+            target_tile_entity: target,
+        }
+    }
+
+    #[test]
+    fn newer_request_supersedes_pending_one() {
+        let (player, enemy) = (entity(1), entity(2));
+        let mut queue = PathRequestQueue::default();
+        queue.push(request(player, entity(10)));
+        queue.push(request(enemy, entity(20)));
+        queue.push(request(player, entity(11)));
+
+        assert_eq!(queue.pending_target(player), Some(entity(11)));
+        assert_eq!(queue.pending_target(enemy), Some(entity(20)));
+        let order: Vec<_> = queue.requests.iter().map(|request| request.entity).collect();
+        assert_eq!(order, vec![enemy, player]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+/// How many upcoming path tiles each character announces to the others.
+pub const RESERVATION_LOOKAHEAD: u32 = 3;
+
+/// Tile occupancy plus short-horizon (space-time) reservations of upcoming steps.
+#[derive(Resource, Default)]
+pub struct TileReservations {
+    /// Tile -> character standing on it or currently stepping onto it
+    pub holders: HashMap<Entity, Entity>,
+    /// Tile -> (character, steps from now) for the next few tiles of every path
+    pub planned: HashMap<Entity, (Entity, u32)>,
+}
+
+impl TileReservations {
+    pub fn holder(&self, tile: Entity) -> Option<Entity> {
+        self.holders.get(&tile).copied()
+    }
+
+    /// A tile is free for `character` if nobody else holds it and nobody else plans
+    /// to enter it sooner than `steps_ahead`.
+    pub fn is_free_for(&self, tile: Entity, character: Entity, steps_ahead: u32) -> bool {
+        if self.holder(tile).is_some_and(|holder| holder != character) {
+            return false;
+        }
+        match self.planned.get(&tile) {
+            Some(&(planner, step)) => planner == character || step > steps_ahead,
+            None => true,
+        }
+    }
+
+    /// Claims `tile` for the next step. Fails if another character holds it.
+    pub fn try_reserve(&mut self, tile: Entity, character: Entity) -> bool {
+        match self.holder(tile) {
+            Some(holder) if holder != character => false,
+            _ => {
+                self.holders.retain(|_, holder| *holder != character);
+                self.holders.insert(tile, character);
+                true
+            }
+        }
+    }
+
+    /// Announces that `character` plans to enter `tile` in `steps_ahead` steps.
+    /// Earlier plans win over later ones.
+    pub fn plan(&mut self, tile: Entity, character: Entity, steps_ahead: u32) {
+        let earlier = self.planned.get(&tile).is_some_and(|&(_, step)| step <= steps_ahead);
+        if !earlier {
+            self.planned.insert(tile, (character, steps_ahead));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw_u32(index).unwrap()
+    }
+
+    #[test]
+    fn tile_held_by_another_character_blocks() {
+        let (tile, holder, other) = (entity(1), entity(10), entity(11));
+        let mut reservations = TileReservations::default();
+        assert!(reservations.try_reserve(tile, holder));
+
+        assert_eq!(reservations.holder(tile), Some(holder));
+        assert!(!reservations.is_free_for(tile, other, RESERVATION_LOOKAHEAD));
+        assert!(!reservations.try_reserve(tile, other));
+    }
+
+    #[test]
+    fn own_reservation_does_not_block() {
+        let (tile, next_tile, character) = (entity(1), entity(2), entity(10));
+        let mut reservations = TileReservations::default();
+        reservations.try_reserve(tile, character);
+        reservations.plan(next_tile, character, 1);
+
+        assert!(reservations.is_free_for(tile, character, 1));
+        assert!(reservations.is_free_for(next_tile, character, 1));
+        assert!(reservations.try_reserve(tile, character));
+
+        // Stepping on releases the tile left behind
+        assert!(reservations.try_reserve(next_tile, character));
+        assert_eq!(reservations.holder(tile), None);
+    }
+
+    #[test]
+    fn planned_steps_block_only_later_arrivals() {
+        let (tile, planner, other) = (entity(1), entity(10), entity(11));
+        let mut reservations = TileReservations::default();
+        reservations.plan(tile, planner, 2);
+
+        // Stepping on before the planner gets there is fine
+        assert!(reservations.is_free_for(tile, other, 1));
+        assert!(!reservations.is_free_for(tile, other, 2));
+        assert!(!reservations.is_free_for(tile, other, RESERVATION_LOOKAHEAD));
+    }
+
+    #[test]
+    fn earlier_plans_win() {
+        let (tile, early, late) = (entity(1), entity(10), entity(11));
+        let mut reservations = TileReservations::default();
+        reservations.plan(tile, late, 3);
+        reservations.plan(tile, early, 1);
+        reservations.plan(tile, late, 2);
+
+        assert_eq!(reservations.planned.get(&tile), Some(&(early, 1)));
+    }
+}
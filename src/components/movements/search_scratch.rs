@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+use std::collections::BinaryHeap;
+
+use crate::components::movements::a_star_movement::AStarNode;
+
+const NO_PARENT: u32 = u32::MAX;
+
+/// Per-node search state in flat arrays indexed by `TileGraph::index`, reused between searches.
+///
+/// An entry only counts when its stamp matches the current generation, so starting a
+/// new search doesn't have to clear the arrays.
+#[derive(Default)]
+pub struct SearchScratch {
+    generation: u32,
+    stamps: Vec<u32>,
+    g_scores: Vec<f32>,
+    came_from: Vec<u32>,
+    closed: Vec<bool>,
+    pub open_set: BinaryHeap<AStarNode<usize>>,
+}
+
+impl SearchScratch {
+    /// Forgets the previous search and makes room for `capacity` nodes.
+    pub fn begin(&mut self, capacity: usize) {
+        if self.stamps.len() < capacity {
+            self.stamps.resize(capacity, 0);
+            self.g_scores.resize(capacity, f32::INFINITY);
+            self.came_from.resize(capacity, NO_PARENT);
+            self.closed.resize(capacity, false);
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamps.fill(0);
+            self.generation = 1;
+        }
+        self.open_set.clear();
+    }
+
+    fn is_current(&self, index: usize) -> bool {
+        self.stamps[index] == self.generation
+    }
+
+    fn touch(&mut self, index: usize) {
+        if !self.is_current(index) {
+            self.stamps[index] = self.generation;
+            self.g_scores[index] = f32::INFINITY;
+            self.came_from[index] = NO_PARENT;
+            self.closed[index] = false;
+        }
+    }
+
+    pub fn g_score(&self, index: usize) -> f32 {
+        if self.is_current(index) { self.g_scores[index] } else { f32::INFINITY }
+    }
+
+    pub fn came_from(&self, index: usize) -> Option<usize> {
+        (self.is_current(index) && self.came_from[index] != NO_PARENT)
+            .then(|| self.came_from[index] as usize)
+    }
+
+    pub fn is_closed(&self, index: usize) -> bool {
+        self.is_current(index) && self.closed[index]
+    }
+
+    pub fn close(&mut self, index: usize) {
+        self.touch(index);
+        self.closed[index] = true;
+    }
+
+    /// Records a better way of reaching `index`.
+    pub fn record(&mut self, index: usize, g_score: f32, came_from: Option<usize>) {
+        self.touch(index);
+        self.g_scores[index] = g_score;
+        self.came_from[index] = came_from.map_or(NO_PARENT, |from| from as u32);
+    }
+
+    /// Indices from the search's start to `index`.
+    pub fn path_to(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![index];
+        let mut current = index;
+        while let Some(previous) = self.came_from(current) {
+            current = previous;
+            path.push(current);
+        }
+        path.reverse();
+        path
+    }
+}
+
+thread_local! {
+    static SEARCH_SCRATCH: RefCell<SearchScratch> = RefCell::new(SearchScratch::default());
+}
+
+/// Runs `search` with this thread's scratch buffers, or fresh ones if a search on this
+/// thread is already using them.
+pub fn with_search_scratch<R>(search: impl FnOnce(&mut SearchScratch) -> R) -> R {
+    SEARCH_SCRATCH.with(|scratch| match scratch.try_borrow_mut() {
+        Ok(mut scratch) => search(&mut scratch),
+        Err(_) => search(&mut SearchScratch::default()),
+    })
+}
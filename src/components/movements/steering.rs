@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+/// Visual offset from the tile path, produced by local steering.
+///
+/// Path following moves the anchor (`Transform.translation - offset`) between tile centres;
+/// the offset only spreads characters apart and never changes their `TilePosition`.
+#[derive(Component, Default)]
+pub struct Steering {
+    pub offset: Vec3,
+}
+
+#[derive(Resource)]
+pub struct SteeringConfig {
+    pub separation_radius: f32, // Characters closer than this push each other apart
+    pub separation_strength: f32,
+    pub obstacle_radius: f32, // Blocked tiles closer than this push characters away
+    pub obstacle_strength: f32,
+    pub max_offset: f32,     // Keeps the offset well inside the current tile
+    pub responsiveness: f32, // How quickly the offset approaches its desired value
+}
+
+impl Default for SteeringConfig {
+    fn default() -> Self {
+        Self {
+            separation_radius: 1.8,
+            separation_strength: 1.0,
+            obstacle_radius: 1.6,
+            obstacle_strength: 1.0,
+            max_offset: 0.8,
+            responsiveness: 6.0,
+        }
+    }
+}
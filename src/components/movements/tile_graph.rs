@@ -0,0 +1,89 @@
+use std::hash::Hash;
+
+use bevy::prelude::*;
+
+use crate::components::TerrainType;
+use crate::components::movements::hierarchical::TileCoord;
+
+/// Grid of tiles as seen by the search algorithms.
+///
+/// Implemented by the `TileGrid` snapshot of the ECS tiles and by the in-memory `GridMap`,
+/// so every search runs on either. Methods taking a node expect one handed out by the
+/// same graph.
+///
+/// Nodes map to dense indices below `node_capacity`, which lets searches keep their
+/// per-node state in flat, reusable buffers instead of hash maps.
+pub trait TileGraph {
+    type Node: Copy + Eq + Hash;
+
+    fn node_capacity(&self) -> usize;
+
+    fn index(&self, node: Self::Node) -> usize;
+
+    fn node_at_index(&self, index: usize) -> Self::Node;
+
+    fn node_at(&self, coord: TileCoord) -> Option<Self::Node>;
+
+    fn coord(&self, node: Self::Node) -> TileCoord;
+
+    /// World position of the tile centre.
+    fn position(&self, node: Self::Node) -> Vec3;
+
+    fn is_walkable(&self, node: Self::Node) -> bool;
+
+    /// Terrain multiplier of the tile, see `TerrainType::movement_cost`.
+    fn movement_cost(&self, node: Self::Node) -> f32;
+
+    /// Tiles adjacent to `node`, diagonals included, that can be stepped onto from it.
+    fn neighbors(&self, node: Self::Node) -> impl Iterator<Item = Self::Node>;
+
+    /// Whether `to` is walkable and the slope up or down to it from the adjacent `from` is
+    /// gentle enough to walk.
+    fn can_step(&self, from: Self::Node, to: Self::Node) -> bool {
+        self.is_walkable(to) && slope_allows(self.position(from), self.position(to))
+    }
+
+    /// Cost of stepping between two adjacent tiles. Climbing costs extra, so the cost
+    /// depends on the direction of the step.
+    fn cost(&self, from: Self::Node, to: Self::Node) -> f32 {
+        let (from_position, to_position) = (self.position(from), self.position(to));
+        traversal_cost(
+            from_position.distance(to_position),
+            self.movement_cost(from),
+            self.movement_cost(to),
+        ) * slope_cost(from_position, to_position)
+    }
+
+    /// Lower bound on the cost between any two tiles.
+    fn heuristic(&self, from: Self::Node, to: Self::Node) -> f32 {
+        self.position(from).distance(self.position(to)) * TerrainType::MIN_MOVEMENT_COST
+    }
+}
+
+/// Steepest rise or drop over horizontal distance that can be walked between adjacent tiles
+pub const MAX_WALKABLE_GRADE: f32 = 0.75;
+
+/// Extra cost per unit of grade when walking uphill; walking downhill costs the same as on
+/// flat ground, which keeps the heuristic admissible
+pub const UPHILL_COST: f32 = 2.0;
+
+/// Cost of stepping between two adjacent tiles on flat ground: distance weighted by the mean
+/// of both tiles' movement cost.
+pub fn traversal_cost(distance: f32, from_cost: f32, to_cost: f32) -> f32 {
+    distance * (from_cost + to_cost) * 0.5
+}
+
+/// Rise over horizontal distance from one tile centre to another; negative downhill.
+pub fn grade(from: Vec3, to: Vec3) -> f32 {
+    let run = from.xz().distance(to.xz());
+    if run <= f32::EPSILON { 0.0 } else { (to.y - from.y) / run }
+}
+
+pub fn slope_allows(from: Vec3, to: Vec3) -> bool {
+    grade(from, to).abs() <= MAX_WALKABLE_GRADE
+}
+
+/// Multiplier on the cost of a step for the climb it takes.
+pub fn slope_cost(from: Vec3, to: Vec3) -> f32 {
+    1.0 + UPHILL_COST * grade(from, to).max(0.0)
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::components::movements::hierarchical::{ClusterGraph, TileCoord};
+use crate::components::movements::tile_graph::TileGraph;
+use crate::components::{NO_TILE, TileGrid, TileIndex};
+
+impl TileGraph for TileGrid {
+    type Node = TileIndex;
+
+    fn node_capacity(&self) -> usize {
+        self.capacity()
+    }
+
+    fn index(&self, node: TileIndex) -> usize {
+        node as usize
+    }
+
+    fn node_at_index(&self, index: usize) -> TileIndex {
+        index as TileIndex
+    }
+
+    fn node_at(&self, coord: TileCoord) -> Option<TileIndex> {
+        self.index_of(coord)
+    }
+
+    fn coord(&self, node: TileIndex) -> TileCoord {
+        self.coord_of(node)
+    }
+
+    fn position(&self, node: TileIndex) -> Vec3 {
+        TileGrid::position(self, node)
+    }
+
+    fn is_walkable(&self, node: TileIndex) -> bool {
+        TileGrid::is_walkable(self, node)
+    }
+
+    fn movement_cost(&self, node: TileIndex) -> f32 {
+        TileGrid::movement_cost(self, node)
+    }
+
+    fn neighbors(&self, node: TileIndex) -> impl Iterator<Item = TileIndex> {
+        TileGrid::neighbors(self, node)
+            .into_iter()
+            .filter(move |&neighbor| neighbor != NO_TILE && self.can_step(node, neighbor))
+    }
+}
+
+/// Latest snapshots shared with in-flight pathfinding tasks.
+///
+/// Rebuilt whenever tiles change; tasks keep the `Arc` they were started with.
+#[derive(Resource, Default)]
+pub struct PathfindingSnapshot {
+    pub tiles: Arc<TileGrid>,
+    pub clusters: Arc<ClusterGraph>,
+}
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+/// Destinations queued after the current one, visited in order.
+#[derive(Component, Default)]
+pub struct Waypoints {
+    pub queue: VecDeque<Entity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatrolMode {
+    Loop,     // A, B, C, A, B, C, ...
+    PingPong, // A, B, C, B, A, B, ...
+}
+
+/// Walks a fixed route of tiles forever, pausing at each stop.
+#[derive(Component)]
+pub struct Patrol {
+    pub route: Vec<Entity>,
+    pub mode: PatrolMode,
+    pub pause: f32, // Seconds spent at each stop
+    pub waited: f32,
+    next: usize,
+    forward: bool,
+}
+
+/// Edits a character's waypoint queue.
+#[derive(Clone, Copy, Debug)]
+pub enum WaypointAction {
+    Append(Entity),
+    Remove(usize),
+    Clear,
+}
+
+#[derive(Message)]
+pub struct WaypointEvent {
+    pub entity: Entity,
+    pub action: WaypointAction,
+}
+
+impl Waypoints {
+    pub fn apply(&mut self, action: WaypointAction) {
+        match action {
+            WaypointAction::Append(tile) => self.queue.push_back(tile),
+            WaypointAction::Remove(index) => {
+                self.queue.remove(index);
+            }
+            WaypointAction::Clear => self.queue.clear(),
+        }
+    }
+}
+
+impl Patrol {
+    pub fn new(route: Vec<Entity>, mode: PatrolMode) -> Self {
+        Self { route, mode, pause: 1.0, waited: 0.0, next: 0, forward: true }
+    }
+
+    /// The stop to head for now; moves on to the one after it.
+    pub fn next_stop(&mut self) -> Option<Entity> {
+        let stop = *self.route.get(self.next)?;
+        let last = self.route.len() - 1;
+        self.next = match self.mode {
+            _ if last == 0 => 0,
+            PatrolMode::Loop => (self.next + 1) % self.route.len(),
+            PatrolMode::PingPong => {
+                if (self.forward && self.next == last) || (!self.forward && self.next == 0) {
+                    self.forward = !self.forward;
+                }
+                if self.forward { self.next + 1 } else { self.next - 1 }
+            }
+        };
+        Some(stop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route() -> Vec<Entity> {
+        (1..=3).map(|index| Entity::from_raw_u32(index).unwrap()).collect()
+    }
+
+    /// Positions in the route of the next `count` stops.
+    fn stops(patrol: &mut Patrol, count: usize) -> Vec<usize> {
+        let route = patrol.route.clone();
+        (0..count)
+            .map(|_| patrol.next_stop().unwrap())
+            .map(|stop| route.iter().position(|&tile| tile == stop).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn loops_back_to_the_first_stop() {
+        let mut patrol = Patrol::new(route(), PatrolMode::Loop);
+        assert_eq!(stops(&mut patrol, 7), [0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn ping_pong_turns_around_at_both_ends() {
+        let mut patrol = Patrol::new(route(), PatrolMode::PingPong);
+        assert_eq!(stops(&mut patrol, 8), [0, 1, 2, 1, 0, 1, 2, 1]);
+    }
+}
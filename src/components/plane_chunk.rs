@@ -1,6 +1,5 @@
-use std::collections::HashMap;
-
 use bevy::prelude::*;
+use crate::components::{TerrainType, TileGrid};
 
 #[derive(Component, Clone, Copy)]
 pub struct PlaneChunk {
@@ -12,11 +11,18 @@ pub struct PlaneChunk {
     pub grid_size: i32,
 }
 
+/// Ground of one tile type within a chunk, drawn with that type's material. Surfaces are
+/// children of their `PlaneChunk` and are what clicks land on.
+#[derive(Component)]
+pub struct ChunkSurface;
+
 #[derive(Component)]
 pub struct Tile {
     pub x: i32,
     pub z: i32,
     pub walkable: bool,
+    pub terrain: TerrainType,
+    pub movement_cost: f32, // Multiplier applied to distance when pathing and to time when walking
     pub selected: bool,
     pub hovered: bool,
     pub idle_color: Color,
@@ -32,13 +38,15 @@ pub struct TilePosition {
 
 #[derive(Resource, Default)]
 pub struct TileRegistry {
-    pub tiles_by_coord: HashMap<(i32, i32), Entity>,
+    pub grid: TileGrid,
 }
 
 #[derive(Message)]
 pub struct TileSelectedEvent {
     pub source_tile_entity: Entity,
     pub target_tile_entity: Entity,
+    pub run: bool,    // Ctrl-click or double-click
+    pub append: bool, // Shift-click queues the tile as a waypoint
 }
 
 impl Default for PlaneChunk {
@@ -53,18 +61,53 @@ impl Default for Tile {
             x: 0,
             z: 0,
             walkable: true,
+            terrain: TerrainType::default(),
+            movement_cost: 1.0,
             selected: false,
             hovered: false,
-            idle_color: Color::srgb(0.0, 0.0, 0.0),
-            selected_color: Color::srgb(1.0, 0.0, 0.0),
-            hovered_color: Color::srgb(0.0, 1.0, 0.0),
+            idle_color: Color::srgba(0.0, 0.0, 0.0, 0.4),
+            selected_color: Color::srgba(1.0, 0.0, 0.0, 0.45),
+            hovered_color: Color::srgba(0.0, 1.0, 0.0, 0.35),
             neighbor_entities: [None; 8],
         }
     }
 }
 
+impl Tile {
+    /// Tint of the tile in its chunk's `TileOverlay`: the hover wins over the selection,
+    /// and blocked tiles are shaded with `idle_color`. `None` leaves the ground as it is.
+    pub fn highlight_color(&self) -> Option<Color> {
+        if self.hovered {
+            Some(self.hovered_color)
+        } else if self.selected {
+            Some(self.selected_color)
+        } else if !self.walkable {
+            Some(self.idle_color)
+        } else {
+            None
+        }
+    }
+}
+
 impl TilePosition {
     pub fn for_entity(entity: Entity) -> Self {
         Self { tile: Some(entity) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hover_outranks_selection_and_blocked_tiles() {
+        let mut tile = Tile::default();
+        assert_eq!(tile.highlight_color(), None);
+        tile.walkable = false;
+        assert_eq!(tile.highlight_color(), Some(tile.idle_color));
+        tile.selected = true;
+        assert_eq!(tile.highlight_color(), Some(tile.selected_color));
+        tile.hovered = true;
+        assert_eq!(tile.highlight_color(), Some(tile.hovered_color));
+    }
+}
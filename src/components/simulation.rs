@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+
+/// Fixed simulation rate for movement, pathfinding and AI
+pub const SIMULATION_HZ: f64 = 60.0;
+
+/// Number of fixed ticks simulated so far.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationTick(pub u64);
+
+/// Draws a `Transform` moved in `FixedUpdate` smoothly between simulation ticks.
+///
+/// Gameplay systems keep reading and writing the plain `Transform`; it holds the
+/// simulated state during `FixedUpdate` and an interpolated one everywhere else.
+#[derive(Component, Default)]
+pub struct TransformInterpolation {
+    /// Simulated transform before the latest tick
+    pub previous: Option<Transform>,
+    /// Simulated transform after the latest tick
+    pub current: Option<Transform>,
+    /// Transform written for rendering; anything else means the entity was moved outside
+    /// the simulation and should snap there
+    pub rendered: Option<Transform>,
+}
+
+impl TransformInterpolation {
+    /// Transform `fraction` of the way from the previous tick to the latest one.
+    pub fn blend(&self, fraction: f32) -> Option<Transform> {
+        let (previous, current) = (self.previous?, self.current?);
+        Some(Transform {
+            translation: previous.translation.lerp(current.translation, fraction),
+            rotation: previous.rotation.slerp(current.rotation, fraction),
+            scale: current.scale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blends_between_ticks() {
+        let interpolation = TransformInterpolation {
+            previous: Some(Transform::from_xyz(0.0, 0.0, 0.0)),
+            current: Some(Transform::from_xyz(2.0, 0.0, 4.0)),
+            rendered: None,
+        };
+        let halfway = interpolation.blend(0.5).unwrap();
+        assert_eq!(halfway.translation, Vec3::new(1.0, 0.0, 2.0));
+        assert_eq!(TransformInterpolation::default().blend(0.5), None);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+/// Name of a tile type, e.g. `Asphalt` or `ToxicSludge`.
+///
+/// What a type looks like and how it walks is data: see `TileTypes` and the
+/// `TileTypeRegistry` built from it. The name is stored inline so tiles stay `Copy`; level,
+/// prefab and tile type files write it as a bare identifier.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TerrainType {
+    len: u8,
+    bytes: [u8; TerrainType::MAX_LEN],
+}
+
+impl TerrainType {
+    /// Longest name a tile type may have, in bytes
+    pub const MAX_LEN: usize = 23;
+
+    /// Cheapest movement cost a tile type may have, used to keep heuristics admissible.
+    pub const MIN_MOVEMENT_COST: f32 = 0.8;
+
+    /// Plain ground, used wherever a level does not say otherwise.
+    pub const GROUND: Self = Self::named("Ground");
+
+    /// Panics on names longer than `MAX_LEN`; use `try_named` for untrusted input.
+    pub const fn named(name: &str) -> Self {
+        match Self::try_named(name) {
+            Some(terrain) => terrain,
+            None => panic!("tile type name is too long"),
+        }
+    }
+
+    pub const fn try_named(name: &str) -> Option<Self> {
+        let source = name.as_bytes();
+        if source.is_empty() || source.len() > Self::MAX_LEN {
+            return None;
+        }
+        let mut bytes = [0; Self::MAX_LEN];
+        let mut i = 0;
+        while i < source.len() {
+            bytes[i] = source[i];
+            i += 1;
+        }
+        Some(Self { len: source.len() as u8, bytes })
+    }
+
+    pub fn name(&self) -> &str {
+        // Only ever built from a whole `&str`
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl Default for TerrainType {
+    fn default() -> Self {
+        Self::GROUND
+    }
+}
+
+impl fmt::Debug for TerrainType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for TerrainType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for TerrainType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NameVisitor;
+
+        impl Visitor<'_> for NameVisitor {
+            type Value = TerrainType;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a tile type name of at most {} bytes", TerrainType::MAX_LEN)
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<TerrainType, E> {
+                TerrainType::try_named(name)
+                    .ok_or_else(|| E::invalid_value(de::Unexpected::Str(name), &self))
+            }
+        }
+
+        deserializer.deserialize_identifier(NameVisitor)
+    }
+}
+
+/// Written as raw RON so the name comes out as the same bare identifier it is read from.
+impl Serialize for TerrainType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let identifier = ron::value::RawValue::from_ron(self.name()).map_err(ser::Error::custom)?;
+        identifier.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn reads_bare_names_as_values_and_keys() {
+        let costs: BTreeMap<TerrainType, f32> =
+            ron::de::from_str("{ Asphalt: 0.8, ToxicSludge: 3.0 }").unwrap();
+        let sludge = TerrainType::named("ToxicSludge");
+        assert_eq!(costs[&sludge], 3.0);
+        assert_eq!(ron::de::from_str::<TerrainType>("Ground"), Ok(TerrainType::GROUND));
+        assert_eq!(format!("{sludge:?}"), "ToxicSludge");
+        assert!(ron::de::from_str::<TerrainType>("AVeryLongTileTypeNameIndeed").is_err());
+        let written = ron::ser::to_string(&sludge).unwrap();
+        assert_eq!(ron::de::from_str::<TerrainType>(&written), Ok(sludge));
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+/// Dense index of a tile in a `TileGrid`.
+pub type TileIndex = u32;
+
+/// Marks a missing tile in index arrays.
+pub const NO_TILE: TileIndex = TileIndex::MAX;
+
+/// Offsets to the eight neighbours, in the order of `Tile.neighbor_entities` (N, NE, E, ... NW).
+pub const NEIGHBOR_DIRECTIONS: [(i32, i32); 8] =
+    [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+/// Height at `point`, blended bilinearly between the four nearest tile centres.
+///
+/// `point` is in tile units with tile centres on whole numbers, so `(2.0, 3.0)` is the centre
+/// of tile (2, 3). Missing tiles take the height of the first one present.
+pub fn bilinear_height(height_of: impl Fn((i32, i32)) -> Option<f32>, point: Vec2) -> f32 {
+    let (x, z) = (point.x.floor() as i32, point.y.floor() as i32);
+    let (tx, tz) = (point.x - x as f32, point.y - z as f32);
+    let corners = [(x, z), (x + 1, z), (x, z + 1), (x + 1, z + 1)].map(&height_of);
+    let fallback = corners.iter().flatten().copied().next().unwrap_or(0.0);
+    let [a, b, c, d] = corners.map(|height| height.unwrap_or(fallback));
+    let bottom = a + (b - a) * tx;
+    let top = c + (d - c) * tx;
+    bottom + (top - bottom) * tz
+}
+
+/// Dense, chunk-aware storage of the tile grid.
+///
+/// Every chunk owns a contiguous block of `chunk_size * chunk_size` slots, so a tile's index
+/// is its chunk's slot times the block size plus its row-major offset inside the chunk.
+/// Walkability, cost, position and neighbour indices live in flat arrays; only finding a
+/// chunk's block needs a hash lookup, and neighbours are followed by index alone.
+/// Tile entities stay the source of truth for picking and metadata.
+///
+/// Chunks can be added and removed as the world streams in and out; the block of a removed
+/// chunk is reused by the next chunk added, so indices of other tiles never move.
+#[derive(Clone, Default)]
+pub struct TileGrid {
+    pub chunk_size: i32,
+    pub tile_size: f32, // World units per tile side
+    chunk_slots: HashMap<(i32, i32), u32>,
+    chunks: Vec<(i32, i32)>,
+    free_slots: Vec<u32>,
+    entities: Vec<Option<Entity>>,
+    walkable: Vec<bool>,
+    movement_costs: Vec<f32>,
+    positions: Vec<Vec3>,
+    neighbors: Vec<[TileIndex; 8]>,
+    index_by_entity: HashMap<Entity, TileIndex>,
+}
+
+impl TileGrid {
+    pub fn new(chunk_size: i32, tile_size: f32) -> Self {
+        Self { chunk_size: chunk_size.max(1), tile_size, ..Default::default() }
+    }
+
+    fn chunk_area(&self) -> usize {
+        (self.chunk_size * self.chunk_size) as usize
+    }
+
+    /// Chunk holding a tile coordinate.
+    pub fn chunk_of(&self, (x, z): (i32, i32)) -> (i32, i32) {
+        (x.div_euclid(self.chunk_size), z.div_euclid(self.chunk_size))
+    }
+
+    fn slot_index(&self, slot: u32, (x, z): (i32, i32)) -> TileIndex {
+        let local = z.rem_euclid(self.chunk_size) * self.chunk_size + x.rem_euclid(self.chunk_size);
+        slot * self.chunk_area() as u32 + local as u32
+    }
+
+    /// Number of index slots, including empty ones in partially filled chunks.
+    pub fn capacity(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.index_by_entity.len()
+    }
+
+    pub fn contains_chunk(&self, chunk: (i32, i32)) -> bool {
+        self.chunk_slots.contains_key(&chunk)
+    }
+
+    /// Chunk coordinates of every chunk with tiles in the grid.
+    pub fn chunk_coords(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.chunk_slots.keys().copied()
+    }
+
+    pub fn index_of(&self, coord: (i32, i32)) -> Option<TileIndex> {
+        let slot = *self.chunk_slots.get(&self.chunk_of(coord))?;
+        let index = self.slot_index(slot, coord);
+        self.entities[index as usize].is_some().then_some(index)
+    }
+
+    pub fn index_of_entity(&self, entity: Entity) -> Option<TileIndex> {
+        self.index_by_entity.get(&entity).copied()
+    }
+
+    pub fn coord_of(&self, index: TileIndex) -> (i32, i32) {
+        let area = self.chunk_area() as u32;
+        let (chunk_x, chunk_z) = self.chunks[(index / area) as usize];
+        let local = (index % area) as i32;
+        (
+            chunk_x * self.chunk_size + local % self.chunk_size,
+            chunk_z * self.chunk_size + local / self.chunk_size,
+        )
+    }
+
+    pub fn entity(&self, index: TileIndex) -> Option<Entity> {
+        self.entities.get(index as usize).copied().flatten()
+    }
+
+    /// Tile entity at a global tile coordinate.
+    pub fn get(&self, coord: (i32, i32)) -> Option<Entity> {
+        self.index_of(coord).and_then(|index| self.entity(index))
+    }
+
+    pub fn is_walkable(&self, index: TileIndex) -> bool {
+        self.walkable.get(index as usize).copied().unwrap_or(false)
+    }
+
+    pub fn movement_cost(&self, index: TileIndex) -> f32 {
+        self.movement_costs.get(index as usize).copied().unwrap_or(1.0)
+    }
+
+    pub fn position(&self, index: TileIndex) -> Vec3 {
+        self.positions.get(index as usize).copied().unwrap_or(Vec3::ZERO)
+    }
+
+    /// Terrain height under `point`, in tile units as taken by `bilinear_height`.
+    pub fn height_at(&self, point: Vec2) -> f32 {
+        let height_of = |coord| self.index_of(coord).map(|index| self.positions[index as usize].y);
+        bilinear_height(height_of, point)
+    }
+
+    /// Neighbour indices in `NEIGHBOR_DIRECTIONS` order, `NO_TILE` where there is none.
+    pub fn neighbors(&self, index: TileIndex) -> [TileIndex; 8] {
+        self.neighbors.get(index as usize).copied().unwrap_or([NO_TILE; 8])
+    }
+
+    /// All tiles as (coordinate, entity), chunk by chunk.
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), Entity)> + '_ {
+        self.entities.iter().enumerate().filter_map(|(index, entity)| {
+            entity.map(|entity| (self.coord_of(index as TileIndex), entity))
+        })
+    }
+
+    /// Adds or replaces a tile. Call `link_neighbors` once all tiles are in.
+    pub fn insert(
+        &mut self,
+        coord: (i32, i32),
+        entity: Entity,
+        position: Vec3,
+        walkable: bool,
+        movement_cost: f32,
+    ) -> TileIndex {
+        let chunk = self.chunk_of(coord);
+        let slot = match self.chunk_slots.get(&chunk) {
+            Some(&slot) => slot,
+            None if !self.free_slots.is_empty() => {
+                let slot = self.free_slots.pop().unwrap_or_default();
+                self.chunk_slots.insert(chunk, slot);
+                self.chunks[slot as usize] = chunk;
+                slot
+            }
+            None => {
+                let slot = self.chunks.len() as u32;
+                self.chunk_slots.insert(chunk, slot);
+                self.chunks.push(chunk);
+                let capacity = self.capacity() + self.chunk_area();
+                self.entities.resize(capacity, None);
+                self.walkable.resize(capacity, false);
+                self.movement_costs.resize(capacity, 1.0);
+                self.positions.resize(capacity, Vec3::ZERO);
+                self.neighbors.resize(capacity, [NO_TILE; 8]);
+                slot
+            }
+        };
+
+        let index = self.slot_index(slot, coord);
+        let i = index as usize;
+        if let Some(previous) = self.entities[i] {
+            self.index_by_entity.remove(&previous);
+        }
+        self.entities[i] = Some(entity);
+        self.walkable[i] = walkable;
+        self.movement_costs[i] = movement_cost;
+        self.positions[i] = position;
+        self.index_by_entity.insert(entity, index);
+        index
+    }
+
+    /// Updates walkability and cost; returns whether anything changed.
+    pub fn set_state(&mut self, index: TileIndex, walkable: bool, movement_cost: f32) -> bool {
+        let i = index as usize;
+        if i >= self.capacity()
+            || (self.walkable[i] == walkable && self.movement_costs[i] == movement_cost)
+        {
+            return false;
+        }
+        self.walkable[i] = walkable;
+        self.movement_costs[i] = movement_cost;
+        true
+    }
+
+    /// Removes every tile of `chunk` and returns their entities, frees its block for reuse.
+    /// Call `link_neighbors_around` afterwards so the tiles bordering it forget them.
+    pub fn remove_chunk(&mut self, chunk: (i32, i32)) -> Vec<Entity> {
+        let Some(slot) = self.chunk_slots.remove(&chunk) else {
+            return Vec::new();
+        };
+        let area = self.chunk_area();
+        let block = slot as usize * area..(slot as usize + 1) * area;
+        let mut removed = Vec::new();
+        for i in block {
+            if let Some(entity) = self.entities[i].take() {
+                self.index_by_entity.remove(&entity);
+                removed.push(entity);
+            }
+            self.walkable[i] = false;
+            self.movement_costs[i] = 1.0;
+            self.neighbors[i] = [NO_TILE; 8];
+        }
+        self.free_slots.push(slot);
+        removed
+    }
+
+    /// Recomputes the neighbour indices of every tile.
+    pub fn link_neighbors(&mut self) {
+        for index in 0..self.capacity() {
+            if self.entities[index].is_some() {
+                self.link_tile(index as TileIndex);
+            }
+        }
+    }
+
+    /// Recomputes the neighbour indices of the tiles in `chunk` and of the ring of tiles
+    /// around it, after the chunk was added or removed. Returns the tiles that were linked.
+    pub fn link_neighbors_around(&mut self, chunk: (i32, i32)) -> Vec<TileIndex> {
+        let size = self.chunk_size;
+        let (min_x, min_z) = (chunk.0 * size - 1, chunk.1 * size - 1);
+        let mut linked = Vec::new();
+        for z in min_z..=min_z + size + 1 {
+            for x in min_x..=min_x + size + 1 {
+                if let Some(index) = self.index_of((x, z)) {
+                    self.link_tile(index);
+                    linked.push(index);
+                }
+            }
+        }
+        linked
+    }
+
+    fn link_tile(&mut self, index: TileIndex) {
+        let (x, z) = self.coord_of(index);
+        let mut neighbors = [NO_TILE; 8];
+        for (slot, (dx, dz)) in NEIGHBOR_DIRECTIONS.into_iter().enumerate() {
+            neighbors[slot] = self.index_of((x + dx, z + dz)).unwrap_or(NO_TILE);
+        }
+        self.neighbors[index as usize] = neighbors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_spanning(min: (i32, i32), max: (i32, i32)) -> TileGrid {
+        let mut grid = TileGrid::new(4, 1.0);
+        let mut next_entity = 1;
+        for x in min.0..=max.0 {
+            for z in min.1..=max.1 {
+                let entity = Entity::from_raw_u32(next_entity).unwrap();
+                next_entity += 1;
+                grid.insert((x, z), entity, Vec3::new(x as f32, 0.0, z as f32), true, 1.0);
+            }
+        }
+        grid.link_neighbors();
+        grid
+    }
+
+    #[test]
+    fn maps_coordinates_to_indices_and_back() {
+        let grid = grid_spanning((-5, -3), (6, 7));
+        for (coord, entity) in grid.iter() {
+            let index = grid.index_of(coord).unwrap();
+            assert_eq!(grid.coord_of(index), coord);
+            assert_eq!(grid.index_of_entity(entity), Some(index));
+        }
+        assert_eq!(grid.tile_count(), 12 * 11);
+        assert_eq!(grid.index_of((7, 0)), None);
+    }
+
+    #[test]
+    fn links_neighbors_across_chunk_borders() {
+        let grid = grid_spanning((0, 0), (7, 7));
+        let corner = grid.index_of((3, 3)).unwrap();
+        let neighbors = grid.neighbors(corner);
+        for (slot, (dx, dz)) in NEIGHBOR_DIRECTIONS.into_iter().enumerate() {
+            assert_eq!(neighbors[slot], grid.index_of((3 + dx, 3 + dz)).unwrap());
+        }
+        let edge = grid.neighbors(grid.index_of((0, 0)).unwrap());
+        assert_eq!(edge.iter().filter(|&&neighbor| neighbor == NO_TILE).count(), 5);
+    }
+
+    #[test]
+    fn blends_heights_between_tile_centres() {
+        let mut grid = grid_spanning((0, 0), (1, 1));
+        for (coord, height) in [((1, 0), 2.0), ((0, 1), 4.0), ((1, 1), 6.0)] {
+            let entity = grid.get(coord).unwrap();
+            grid.insert(coord, entity, Vec3::new(0.0, height, 0.0), true, 1.0);
+        }
+        assert_eq!(grid.height_at(Vec2::new(0.0, 0.0)), 0.0);
+        assert_eq!(grid.height_at(Vec2::new(1.0, 1.0)), 6.0);
+        assert_eq!(grid.height_at(Vec2::new(0.5, 0.0)), 1.0);
+        assert_eq!(grid.height_at(Vec2::new(0.5, 0.5)), 3.0);
+        // Past the edge the nearest tiles still count
+        assert_eq!(grid.height_at(Vec2::new(-0.5, 1.0)), 4.0);
+    }
+
+    #[test]
+    fn removes_and_relinks_streamed_chunks() {
+        let mut grid = grid_spanning((0, 0), (7, 7));
+        let capacity = grid.capacity();
+        assert_eq!(grid.remove_chunk((1, 0)).len(), 16);
+        grid.link_neighbors_around((1, 0));
+        assert_eq!(grid.get((4, 0)), None);
+        assert_eq!(grid.neighbors(grid.index_of((3, 0)).unwrap())[2], NO_TILE);
+
+        // A chunk streamed in elsewhere reuses the freed block and links to its neighbours
+        for x in 0..4 {
+            for z in 8..12 {
+                let entity = Entity::from_raw_u32(1000 + (x * 4 + z) as u32).unwrap();
+                grid.insert((x, z), entity, Vec3::ZERO, true, 1.0);
+            }
+        }
+        let linked = grid.link_neighbors_around((0, 2));
+        assert_eq!(grid.capacity(), capacity);
+        assert_eq!(grid.tile_count(), 64);
+        let below = grid.index_of((2, 7)).unwrap();
+        assert!(linked.contains(&below));
+        assert_eq!(grid.neighbors(below)[0], grid.index_of((2, 8)).unwrap());
+        assert_eq!(grid.coord_of(grid.index_of((2, 8)).unwrap()), (2, 8));
+    }
+}
@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+/// The tile under the pointer and the player's chosen destination.
+///
+/// Mirrored into the `hovered` and `selected` flags of those tiles, so gameplay code can
+/// query either.
+#[derive(Resource, Default, Clone, PartialEq)]
+pub struct TileHighlight {
+    pub hovered: Option<Entity>,
+    pub selected: Option<Entity>,
+}
+
+/// Overlay drawn just above a chunk's ground, tinting its hovered, selected and blocked
+/// tiles. A child of its `PlaneChunk`, rebuilt whenever one of the chunk's tiles changes.
+#[derive(Component)]
+pub struct TileOverlay;
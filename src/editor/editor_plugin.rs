@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::editor::editor_state::{EditorMode, EditorState, LevelEditedEvent};
+use crate::editor::editor_system::{
+    apply_level_edits, choose_editor_tool, draw_level_markers, handle_editor_shortcuts,
+    paint_level, toggle_editor_mode,
+};
+use crate::editor::level_edit::EditHistory;
+use crate::level::LevelAssets;
+use crate::plugins::InputSet;
+use crate::systems::animation::PlayerLoadingState;
+
+/// In-game level editor. F2 switches to edit mode, where dragging over the ground paints
+/// the level with the current tool instead of moving the player, and Ctrl+S saves it.
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<EditorMode>()
+            .init_resource::<EditorState>()
+            .init_resource::<EditHistory>()
+            .add_message::<LevelEditedEvent>()
+            .add_systems(
+                Update,
+                (
+                    toggle_editor_mode.run_if(in_state(PlayerLoadingState::Ready)),
+                    (choose_editor_tool, paint_level, handle_editor_shortcuts, draw_level_markers)
+                        .chain()
+                        .run_if(in_state(EditorMode::Edit)),
+                    apply_level_edits,
+                )
+                    .chain()
+                    .in_set(InputSet)
+                    .run_if(resource_exists::<LevelAssets>),
+            );
+    }
+}
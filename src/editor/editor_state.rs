@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::components::TerrainType;
+use crate::editor::level_edit::LevelEdit;
+use crate::level::Level;
+use crate::level::level_asset::LevelTile;
+use crate::level::prefab::SpawnMarker;
+
+/// Props the editor can place, cycled with Q and E.
+pub const EDITOR_PROPS: [&str; 4] = ["ammo_crate", "cash_register", "fuel_pump", "wrecked_truck"];
+
+/// Whether the level is being played or edited; F2 switches between the two.
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EditorMode {
+    #[default]
+    Play,
+    Edit,
+}
+
+/// What dragging across the level does in edit mode, picked with keys 1 to 5.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditorTool {
+    #[default]
+    Walkability,
+    Terrain,
+    PlayerSpawn,
+    EnemyMarker,
+    Prop,
+}
+
+/// Tool and brush of the level editor.
+#[derive(Resource, Debug, Default)]
+pub struct EditorState {
+    pub tool: EditorTool,
+    pub terrain: TerrainType,
+    /// Index into `EDITOR_PROPS`
+    pub prop: usize,
+    /// Whether the stroke in progress clears what it is dragged over. Toggling tools decide
+    /// on the tile the stroke starts on; `None` between strokes
+    pub clearing: Option<bool>,
+}
+
+/// Tiles whose ground, props or markers the editor changed.
+#[derive(Message)]
+pub struct LevelEditedEvent {
+    pub coords: Vec<(i32, i32)>,
+}
+
+impl EditorState {
+    pub fn prop_name(&self) -> &'static str {
+        EDITOR_PROPS[self.prop % EDITOR_PROPS.len()]
+    }
+
+    /// What the current tool does to the tile at `coord`, or `None` when it would change
+    /// nothing.
+    pub fn edit_at(&mut self, level: &Level, coord: (i32, i32)) -> Option<LevelEdit> {
+        let tile = level.tile(coord);
+        match self.tool {
+            EditorTool::Walkability => {
+                let clearing = *self.clearing.get_or_insert(tile.walkable);
+                LevelEdit::tile(level, coord, LevelTile { walkable: !clearing, ..tile })
+            }
+            EditorTool::Terrain => {
+                LevelEdit::tile(level, coord, LevelTile { terrain: self.terrain, ..tile })
+            }
+            EditorTool::PlayerSpawn => LevelEdit::player_spawn(level, coord),
+            EditorTool::EnemyMarker => {
+                let marked = level
+                    .spawn_markers
+                    .iter()
+                    .any(|&(marked, marker)| marked == coord && marker == SpawnMarker::Enemy);
+                let clearing = *self.clearing.get_or_insert(marked);
+                LevelEdit::marker(level, coord, (!clearing).then_some(SpawnMarker::Enemy))
+            }
+            EditorTool::Prop => {
+                let placed = level.props.iter().any(|prop| prop.coord == coord);
+                let clearing = *self.clearing.get_or_insert(placed);
+                let prop = (!clearing).then(|| self.prop_name().to_string());
+                LevelEdit::prop(level, coord, prop)
+            }
+        }
+    }
+
+    pub fn end_stroke(&mut self) {
+        self.clearing = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn strokes_keep_the_toggle_they_start_with() {
+        let blocked = LevelTile { walkable: false, ..default() };
+        let mut level = Level {
+            chunk_cols: 1,
+            chunk_rows: 1,
+            tiles_per_chunk: 3,
+            legend: BTreeMap::from([('.', LevelTile::default()), ('#', blocked)]),
+            tiles: vec!["#..".to_string(), "...".to_string(), "...".to_string()],
+            ..default()
+        };
+        let mut editor = EditorState::default();
+        // Starting on a blocked tile unblocks, and leaves open tiles alone
+        let edit = editor.edit_at(&level, (0, 0)).unwrap();
+        edit.apply(&mut level);
+        assert_eq!(editor.edit_at(&level, (1, 0)), None);
+        editor.end_stroke();
+        assert!(level.tile((0, 0)).walkable);
+        // Starting on an open tile blocks
+        assert!(matches!(
+            editor.edit_at(&level, (1, 0)),
+            Some(LevelEdit::Tile { after: LevelTile { walkable: false, .. }, .. })
+        ));
+
+        editor.end_stroke();
+        editor.tool = EditorTool::Prop;
+        editor.prop = 2;
+        assert_eq!(
+            editor.edit_at(&level, (2, 2)),
+            Some(LevelEdit::Prop { coord: (2, 2), before: None, after: Some("fuel_pump".into()) })
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::ecs::system::SystemParam;
+use bevy::picking::pointer::PointerButton;
+use bevy::prelude::*;
+
+use crate::components::{PlaneChunk, Tile, TileRegistry};
+use crate::editor::editor_state::{
+    EDITOR_PROPS, EditorMode, EditorState, EditorTool, LevelEditedEvent,
+};
+use crate::editor::level_edit::{EditHistory, LevelEdit, save_level};
+use crate::level::prefab::SpawnMarker;
+use crate::level::tile_types::TileTypeRegistry;
+use crate::level::{Level, LevelAssets};
+use crate::systems::chunk_streaming_system::StreamingContext;
+use crate::systems::plane_chunk_system::TilePicker;
+
+/// Where a level that was not loaded from a file, like a generated one, is saved.
+const UNSAVED_LEVEL_PATH: &str = "levels/edited.level.ron";
+
+/// The level being edited and the edits made to it.
+#[derive(SystemParam)]
+pub struct LevelEditor<'w> {
+    state: ResMut<'w, EditorState>,
+    history: ResMut<'w, EditHistory>,
+    level_assets: Res<'w, LevelAssets>,
+    levels: ResMut<'w, Assets<Level>>,
+    edited_writer: MessageWriter<'w, LevelEditedEvent>,
+}
+
+impl LevelEditor<'_> {
+    /// Uses the current tool on the tile at `coord`. Tiles outside the level are wilderness,
+    /// which is not saved, so they are left alone.
+    fn paint(&mut self, coord: (i32, i32)) {
+        let Some(level) = self.levels.get_mut(&self.level_assets.level) else {
+            return;
+        };
+        if !level.contains(coord) {
+            return;
+        }
+        if let Some(edit) = self.state.edit_at(level, coord) {
+            self.history.apply(level, edit);
+            self.edited_writer.write(LevelEditedEvent { coords: vec![coord] });
+        }
+    }
+
+    fn end_stroke(&mut self) {
+        self.state.end_stroke();
+        self.history.end_stroke();
+    }
+
+    fn undo(&mut self) {
+        let Some(level) = self.levels.get_mut(&self.level_assets.level) else {
+            return;
+        };
+        let edits = self.history.undo(level);
+        self.notify(&edits);
+    }
+
+    fn redo(&mut self) {
+        let Some(level) = self.levels.get_mut(&self.level_assets.level) else {
+            return;
+        };
+        let edits = self.history.redo(level);
+        self.notify(&edits);
+    }
+
+    fn notify(&mut self, edits: &[LevelEdit]) {
+        if !edits.is_empty() {
+            let coords = edits.iter().flat_map(LevelEdit::coords).collect();
+            self.edited_writer.write(LevelEditedEvent { coords });
+        }
+    }
+
+    /// Writes the level back to the file it was loaded from.
+    fn save(&self, asset_server: &AssetServer) {
+        let Some(level) = self.levels.get(&self.level_assets.level) else {
+            return;
+        };
+        let path = asset_server.get_path(&self.level_assets.level);
+        let asset_path = path.as_ref().map_or(UNSAVED_LEVEL_PATH.as_ref(), |path| path.path());
+        let file = FileAssetReader::get_base_path().join("assets").join(asset_path);
+        match save_level(level, &file) {
+            Ok(()) => info!("Saved level to {}", file.display()),
+            Err(error) => error!("Could not save level to {}: {}", file.display(), error),
+        }
+    }
+}
+
+pub fn toggle_editor_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Res<State<EditorMode>>,
+    mut next_mode: ResMut<NextState<EditorMode>>,
+) {
+    if !keys.just_pressed(KeyCode::F2) {
+        return;
+    }
+    let next = match mode.get() {
+        EditorMode::Play => EditorMode::Edit,
+        EditorMode::Edit => EditorMode::Play,
+    };
+    info!("Switching to {:?} mode", next);
+    next_mode.set(next);
+}
+
+/// Picks the tool with keys 1 to 5 and cycles the terrain or prop it paints with Q and E.
+pub fn choose_editor_tool(
+    keys: Res<ButtonInput<KeyCode>>,
+    tile_types: Res<TileTypeRegistry>,
+    mut editor: ResMut<EditorState>,
+) {
+    let tools = [
+        (KeyCode::Digit1, EditorTool::Walkability),
+        (KeyCode::Digit2, EditorTool::Terrain),
+        (KeyCode::Digit3, EditorTool::PlayerSpawn),
+        (KeyCode::Digit4, EditorTool::EnemyMarker),
+        (KeyCode::Digit5, EditorTool::Prop),
+    ];
+    if let Some(&(_, tool)) = tools.iter().find(|(key, _)| keys.just_pressed(*key)) {
+        editor.tool = tool;
+    }
+    let step = match (keys.just_pressed(KeyCode::KeyQ), keys.just_pressed(KeyCode::KeyE)) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => 0,
+    };
+    if step != 0 && editor.tool == EditorTool::Prop {
+        let count = EDITOR_PROPS.len() as i32;
+        editor.prop = (editor.prop as i32 + step).rem_euclid(count) as usize;
+    } else if step != 0 {
+        let terrains = tile_types.terrains();
+        let current = terrains.iter().position(|&terrain| terrain == editor.terrain).unwrap_or(0);
+        let next = (current as i32 + step).rem_euclid(terrains.len().max(1) as i32) as usize;
+        editor.terrain = terrains.get(next).copied().unwrap_or_default();
+    }
+    if editor.is_changed() {
+        info!(
+            "Editor tool {:?}, terrain {}, prop {}",
+            editor.tool,
+            editor.terrain,
+            editor.prop_name()
+        );
+    }
+}
+
+/// Paints with the current tool on every tile the pointer presses or drags across. Releasing
+/// the button ends the stroke, which is undone as one.
+pub fn paint_level(
+    mut press_events: MessageReader<Pointer<Press>>,
+    mut move_events: MessageReader<Pointer<Move>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    tile_picker: TilePicker,
+    mut level_editor: LevelEditor,
+) {
+    let pressed = press_events
+        .read()
+        .filter(|event| event.button == PointerButton::Primary)
+        .map(|event| (event.entity, event.hit.position));
+    let dragged = move_events
+        .read()
+        .filter(|_| mouse.pressed(MouseButton::Left))
+        .map(|event| (event.entity, event.hit.position));
+    let hits: Vec<(Entity, Option<Vec3>)> = pressed.chain(dragged).collect();
+    for (entity, position) in hits {
+        if let Some((coord, _)) = tile_picker.pick(entity, position) {
+            level_editor.paint(coord);
+        }
+    }
+    if !mouse.pressed(MouseButton::Left) {
+        level_editor.end_stroke();
+    }
+}
+
+/// Ctrl+Z undoes, Ctrl+Y or Ctrl+Shift+Z redoes and Ctrl+S saves the level.
+pub fn handle_editor_shortcuts(
+    keys: Res<ButtonInput<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut level_editor: LevelEditor,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keys.just_pressed(KeyCode::KeyY) || (shift && keys.just_pressed(KeyCode::KeyZ)) {
+        level_editor.redo();
+    } else if keys.just_pressed(KeyCode::KeyZ) {
+        level_editor.undo();
+    } else if keys.just_pressed(KeyCode::KeyS) {
+        level_editor.save(&asset_server);
+    }
+}
+
+/// Brings the loaded tiles in line with the edited level. Tile changes flow on into
+/// pathfinding as usual; marking their chunks changed redraws the ground and props.
+pub fn apply_level_edits(
+    mut edited_events: MessageReader<LevelEditedEvent>,
+    context: StreamingContext,
+    tile_types: Res<TileTypeRegistry>,
+    tile_registry: Res<TileRegistry>,
+    mut tile_query: Query<&mut Tile>,
+    mut chunk_query: Query<&mut PlaneChunk>,
+) {
+    let coords: HashSet<(i32, i32)> =
+        edited_events.read().flat_map(|event| event.coords.iter().copied()).collect();
+    if coords.is_empty() {
+        return;
+    }
+    let Some(level) = context.level() else {
+        return;
+    };
+    let grid = &tile_registry.grid;
+    for &coord in &coords {
+        let Some(mut tile) = grid.get(coord).and_then(|entity| tile_query.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        let level_tile = context.tile(level, coord);
+        let tile_type = tile_types.get(level_tile.terrain);
+        let walkable = level_tile.walkable && tile_type.walkable;
+        if tile.walkable != walkable
+            || tile.terrain != level_tile.terrain
+            || tile.movement_cost != tile_type.movement_cost
+        {
+            tile.walkable = walkable;
+            tile.terrain = level_tile.terrain;
+            tile.movement_cost = tile_type.movement_cost;
+        }
+    }
+    let chunks: HashSet<(i32, i32)> = coords.iter().map(|&coord| grid.chunk_of(coord)).collect();
+    for mut chunk in chunk_query.iter_mut() {
+        if chunks.contains(&(chunk.x, chunk.z)) {
+            chunk.set_changed();
+        }
+    }
+}
+
+/// Shows the player spawn and the spawn markers while editing.
+pub fn draw_level_markers(
+    mut gizmos: Gizmos,
+    context: StreamingContext,
+    tile_registry: Res<TileRegistry>,
+) {
+    let Some(level) = context.level() else {
+        return;
+    };
+    let grid = &tile_registry.grid;
+    let position = |coord| grid.index_of(coord).map(|index| grid.position(index) + Vec3::Y);
+    if let Some(spawn) = position(level.player_spawn()) {
+        gizmos.sphere(Isometry3d::from_translation(spawn), 0.6, Color::srgb(0.2, 0.5, 1.0));
+    }
+    for &(coord, marker) in &level.spawn_markers {
+        let color = match marker {
+            SpawnMarker::Enemy => Color::srgb(1.0, 0.2, 0.2),
+            SpawnMarker::Loot => Color::srgb(1.0, 0.85, 0.2),
+        };
+        if let Some(marker_position) = position(coord) {
+            gizmos.sphere(Isometry3d::from_translation(marker_position), 0.4, color);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::level::Level;
+use crate::level::level_asset::{LevelLayoutError, LevelTile};
+use crate::level::prefab::{PlacedProp, SpawnMarker};
+
+/// One change to a level, remembering what it replaced so it can be undone.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelEdit {
+    Tile { coord: (i32, i32), before: LevelTile, after: LevelTile },
+    Prop { coord: (i32, i32), before: Option<String>, after: Option<String> },
+    Marker { coord: (i32, i32), before: Option<SpawnMarker>, after: Option<SpawnMarker> },
+    PlayerSpawn { before: Option<(i32, i32)>, after: Option<(i32, i32)> },
+}
+
+/// Strokes of edits that can be undone and redone. A stroke is everything painted between
+/// pressing and releasing the mouse button, and is undone as a whole.
+#[derive(Resource, Default, Debug)]
+pub struct EditHistory {
+    undo: Vec<Vec<LevelEdit>>,
+    redo: Vec<Vec<LevelEdit>>,
+    stroke: Vec<LevelEdit>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LevelSaveError {
+    #[error("invalid level layout: {0}")]
+    Layout(#[from] LevelLayoutError),
+    #[error("could not write level as RON: {0}")]
+    Ron(#[from] ron::Error),
+    #[error("could not write level file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl LevelEdit {
+    /// Sets the tile at `coord`, or `None` when it already is `tile`.
+    pub fn tile(level: &Level, coord: (i32, i32), tile: LevelTile) -> Option<Self> {
+        let before = level.tile(coord);
+        (before != tile).then_some(LevelEdit::Tile { coord, before, after: tile })
+    }
+
+    /// Places or removes the prop at `coord`, or `None` when nothing would change.
+    pub fn prop(level: &Level, coord: (i32, i32), prop: Option<String>) -> Option<Self> {
+        let before = level
+            .props
+            .iter()
+            .find(|placed| placed.coord == coord)
+            .map(|placed| placed.name.clone());
+        (before != prop).then_some(LevelEdit::Prop { coord, before, after: prop })
+    }
+
+    /// Places or removes the spawn marker at `coord`, or `None` when nothing would change.
+    pub fn marker(level: &Level, coord: (i32, i32), marker: Option<SpawnMarker>) -> Option<Self> {
+        let before = level
+            .spawn_markers
+            .iter()
+            .find(|(marked, _)| *marked == coord)
+            .map(|&(_, marker)| marker);
+        (before != marker).then_some(LevelEdit::Marker { coord, before, after: marker })
+    }
+
+    /// Moves the player spawn to `coord`, or `None` when it is already there.
+    pub fn player_spawn(level: &Level, coord: (i32, i32)) -> Option<Self> {
+        let before = level.player_spawn;
+        (before != Some(coord)).then_some(LevelEdit::PlayerSpawn { before, after: Some(coord) })
+    }
+
+    /// Tiles whose ground, props or markers the edit changes.
+    pub fn coords(&self) -> Vec<(i32, i32)> {
+        match *self {
+            LevelEdit::Tile { coord, .. }
+            | LevelEdit::Prop { coord, .. }
+            | LevelEdit::Marker { coord, .. } => vec![coord],
+            LevelEdit::PlayerSpawn { before, after } => before.into_iter().chain(after).collect(),
+        }
+    }
+
+    pub fn apply(&self, level: &mut Level) {
+        match self {
+            LevelEdit::Tile { coord, after, .. } => level.set_tile(*coord, *after),
+            LevelEdit::Prop { coord, after, .. } => {
+                level.props.retain(|placed| placed.coord != *coord);
+                if let Some(name) = after {
+                    level.props.push(PlacedProp { coord: *coord, name: name.clone() });
+                }
+            }
+            LevelEdit::Marker { coord, after, .. } => {
+                level.spawn_markers.retain(|(marked, _)| marked != coord);
+                if let Some(marker) = after {
+                    level.spawn_markers.push((*coord, *marker));
+                }
+            }
+            LevelEdit::PlayerSpawn { after, .. } => level.player_spawn = *after,
+        }
+    }
+
+    /// The edit that undoes this one.
+    pub fn reverted(&self) -> Self {
+        match self.clone() {
+            LevelEdit::Tile { coord, before, after } => {
+                LevelEdit::Tile { coord, before: after, after: before }
+            }
+            LevelEdit::Prop { coord, before, after } => {
+                LevelEdit::Prop { coord, before: after, after: before }
+            }
+            LevelEdit::Marker { coord, before, after } => {
+                LevelEdit::Marker { coord, before: after, after: before }
+            }
+            LevelEdit::PlayerSpawn { before, after } => {
+                LevelEdit::PlayerSpawn { before: after, after: before }
+            }
+        }
+    }
+}
+
+impl EditHistory {
+    /// Applies `edit` to `level` as part of the stroke in progress.
+    pub fn apply(&mut self, level: &mut Level, edit: LevelEdit) {
+        edit.apply(level);
+        self.stroke.push(edit);
+        self.redo.clear();
+    }
+
+    /// Closes the stroke in progress, so the next edit starts a new one.
+    pub fn end_stroke(&mut self) {
+        if !self.stroke.is_empty() {
+            self.undo.push(std::mem::take(&mut self.stroke));
+        }
+    }
+
+    /// Reverts the last stroke, returning the edits that did so.
+    pub fn undo(&mut self, level: &mut Level) -> Vec<LevelEdit> {
+        self.end_stroke();
+        let Some(stroke) = self.undo.pop() else {
+            return Vec::new();
+        };
+        let reverted: Vec<LevelEdit> = stroke.iter().rev().map(LevelEdit::reverted).collect();
+        for edit in &reverted {
+            edit.apply(level);
+        }
+        self.redo.push(stroke);
+        reverted
+    }
+
+    /// Applies the last undone stroke again, returning its edits.
+    pub fn redo(&mut self, level: &mut Level) -> Vec<LevelEdit> {
+        self.end_stroke();
+        let Some(stroke) = self.redo.pop() else {
+            return Vec::new();
+        };
+        for edit in &stroke {
+            edit.apply(level);
+        }
+        self.undo.push(stroke.clone());
+        stroke
+    }
+}
+
+/// Writes `level` to `file` as a `.level.ron`, refusing layouts the loader would reject.
+pub fn save_level(level: &Level, file: &Path) -> Result<(), LevelSaveError> {
+    level.validate()?;
+    std::fs::write(file, level.to_ron()?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::components::TerrainType;
+
+    fn level() -> Level {
+        Level {
+            chunk_cols: 1,
+            chunk_rows: 1,
+            tiles_per_chunk: 3,
+            tile_size: 2.0,
+            legend: BTreeMap::from([('.', LevelTile::default())]),
+            tiles: vec!["...".to_string(); 3],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn undoes_and_redoes_whole_strokes() {
+        let mut level = level();
+        let mut history = EditHistory::default();
+        let sand = LevelTile { terrain: TerrainType::named("Sand"), ..default() };
+        for coord in [(0, 0), (1, 0)] {
+            let edit = LevelEdit::tile(&level, coord, sand).unwrap();
+            history.apply(&mut level, edit);
+        }
+        history.end_stroke();
+        let edit = LevelEdit::prop(&level, (2, 2), Some("fuel_pump".to_string())).unwrap();
+        history.apply(&mut level, edit);
+        // Painting over what is already there changes nothing
+        assert_eq!(LevelEdit::tile(&level, (1, 0), sand), None);
+
+        assert_eq!(history.undo(&mut level).len(), 1);
+        assert!(level.props.is_empty());
+        let reverted = history.undo(&mut level);
+        assert_eq!(
+            reverted.iter().flat_map(LevelEdit::coords).collect::<Vec<_>>(),
+            [(1, 0), (0, 0)]
+        );
+        assert_eq!(level.tile((0, 0)), LevelTile::default());
+        assert!(history.undo(&mut level).is_empty());
+
+        history.redo(&mut level);
+        assert_eq!(level.tile((1, 0)), sand);
+        // A new edit forgets what was undone
+        let edit = LevelEdit::player_spawn(&level, (2, 0)).unwrap();
+        history.apply(&mut level, edit);
+        assert!(history.redo(&mut level).is_empty());
+        assert_eq!(level.player_spawn(), (2, 0));
+    }
+}
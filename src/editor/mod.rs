@@ -0,0 +1,7 @@
+mod editor_plugin;
+pub mod editor_state;
+mod editor_system;
+pub mod level_edit;
+
+pub use editor_plugin::EditorPlugin;
+pub use editor_state::EditorMode;
@@ -15,11 +15,6 @@ pub struct EnemyGizmo {
     pub size: f32,
 }
 
-#[derive(Component, Clone)]
-pub struct EnemyLastMovementTime {
-    pub time: f32,
-}
-
 #[derive(Component)]
 pub struct EnemySpawned;
 
@@ -35,6 +30,12 @@ impl Default for Enemy {
     }
 }
 
+impl EnemyGizmo {
+    pub fn patrol() -> Self {
+        Self { color: Color::srgb(1.0, 0.5, 0.0), size: 1.0 }
+    }
+}
+
 impl Default for EnemyGizmo {
     fn default() -> Self {
         Self {
@@ -42,10 +43,4 @@ impl Default for EnemyGizmo {
             size: 1.0,
         }
     }
-}
-
-impl Default for EnemyLastMovementTime {
-    fn default() -> Self {
-        Self { time: 0.0 }
-    }
 }
\ No newline at end of file
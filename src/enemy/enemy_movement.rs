@@ -1,30 +1,58 @@
+use crate::components::movements::flow_field::FlowField;
+use crate::components::movements::movement::Movement;
+use crate::components::movements::reservation::TileReservations;
+use crate::components::movements::waypoints::Patrol;
+use crate::components::TilePosition;
+use crate::components::movements::tile_graph::TileGraph;
+use crate::enemy::enemy_components::Enemy;
 use bevy::prelude::*;
-use crate::components::movements::movement::{MoveRequestEvent, MovementType};
-use crate::components::{MovementState, TilePosition};
-use crate::player::player::Player;
-use crate::enemy::enemy_components::{Enemy, EnemyLastMovementTime};
 
+/// Enemies driven by the flow field
+type Chaser = (With<Enemy>, Without<Patrol>);
+
+/// Feeds each enemy its next tile from the shared flow field.
+///
+/// One tile of lookahead is queued while the current step is still in progress,
+/// so enemies keep moving without waiting a frame at every tile centre. When the
+/// flow field's step is taken, any other free neighbour closer to the player that can be
+/// stepped onto is used, which spreads the horde around the player instead of stacking it
+/// on one tile.
+/// Patrolling enemies follow their route instead.
 pub fn update_enemy_movement(
-    mut enemies: Query<(Entity, &MovementState, &mut EnemyLastMovementTime, &TilePosition), With<Enemy>>,
-    player: Query<&TilePosition, With<Player>>,
-    mut movement_request_writer: MessageWriter<MoveRequestEvent>,
-    time: Res<Time>,
+    mut enemies: Query<(Entity, &mut Movement, &TilePosition), Chaser>,
+    flow_field: Res<FlowField>,
+    mut reservations: ResMut<TileReservations>,
 ) {
-    if let Ok(player_tile) = player.single() {
-        if let Some(player_tile_entity) = player_tile.tile {
-            for (entity, movement_state, mut last_movement_time, source_tile) in enemies.iter_mut() {
-                // if movement_state == &MovementState::Walking {
-                    if (time.elapsed_secs() - last_movement_time.time) > 1.0 {
-                        movement_request_writer.write(MoveRequestEvent {
-                            entity,
-                            movement_type: MovementType::ASTAR,
-                            source_tile_entity: source_tile.tile.unwrap(),
-                            target_tile_entity: player_tile_entity,
-                        });
-                        last_movement_time.time = time.elapsed_secs();
-                    }
-                // }
-            }
+    for (entity, mut movement, tile_position) in enemies.iter_mut() {
+        if !movement.path.is_empty() {
+            continue;
+        }
+        let Some(current_tile) = tile_position.tile else {
+            continue;
+        };
+        let (Some(current_distance), Some(current_index)) =
+            (flow_field.distance(current_tile), flow_field.tiles.index_of_entity(current_tile))
+        else {
+            continue;
+        };
+
+        let next_tile = flow_field
+            .next_tile(current_tile)
+            .filter(|&next_tile| reservations.is_free_for(next_tile, entity, 1))
+            .or_else(|| {
+                TileGraph::neighbors(flow_field.tiles.as_ref(), current_index)
+                    .filter_map(|neighbor| flow_field.tiles.entity(neighbor))
+                    .filter_map(|neighbor| flow_field.distance(neighbor).map(|d| (neighbor, d)))
+                    .filter(|&(neighbor, distance)| {
+                        distance < current_distance && reservations.is_free_for(neighbor, entity, 1)
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(neighbor, _)| neighbor)
+            });
+
+        if let Some(next_tile) = next_tile {
+            reservations.plan(next_tile, entity, 1);
+            movement.path.push_back(next_tile);
         }
     }
-}
\ No newline at end of file
+}
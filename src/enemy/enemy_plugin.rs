@@ -1,14 +1,32 @@
+use crate::components::movements::flow_field::FlowField;
 use crate::enemy::enemy_movement::{update_enemy_movement};
-use crate::enemy::enemy_system::{draw_enemy_gizmo, init_enemy};
+use crate::enemy::enemy_system::{despawn_unloaded_enemies, draw_enemy_gizmo, init_enemy};
+use crate::level::LevelAssets;
+use crate::plugins::PlayerSystemSet;
+use crate::systems::movement::flow_field::update_flow_field;
+use crate::systems::chunk_streaming_system::link_streamed_chunks;
+use crate::systems::movement::path_request_system::dispatch_path_requests;
 use bevy::prelude::*;
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<FlowField>();
         app.add_systems(
             Update,
-            (init_enemy, draw_enemy_gizmo, update_enemy_movement),
+            (init_enemy.run_if(resource_exists::<LevelAssets>), draw_enemy_gizmo),
+        );
+        app.add_systems(
+            FixedUpdate,
+            despawn_unloaded_enemies.after(link_streamed_chunks).before(PlayerSystemSet::Input),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (update_flow_field, update_enemy_movement)
+                .chain()
+                .after(dispatch_path_requests)
+                .in_set(PlayerSystemSet::Movement),
         );
     }
 }
@@ -1,11 +1,18 @@
 use crate::player::player::{PlayerStartupTileSelectedEvent};
-use crate::components::{MovementState, Tile, TilePosition, TileRegistry};
-use crate::enemy::enemy_components::{Enemy, EnemyGizmo, EnemyLastMovementTime, EnemySpawned};
+use crate::components::{
+    MovementState, Tile, TileGrid, TilePosition, TileRegistry, TransformInterpolation,
+};
+use crate::enemy::enemy_components::{Enemy, EnemyGizmo, EnemySpawned};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use rand::Rng;
-use std::collections::HashMap;
 use crate::components::movements::movement::{Movement, MovementSpeed};
-use crate::shared::CharacterType;
+use crate::components::movements::steering::Steering;
+use crate::components::chunk_streaming::ChunksStreamedEvent;
+use crate::components::movements::waypoints::{Patrol, PatrolMode};
+use crate::level::prefab::SpawnMarker;
+use crate::level::{Level, LevelAssets};
+use crate::shared::{CharacterType, GameRng, RngStream};
 use crate::systems::animation::PlayerWithAssetsSpawned;
 
 pub struct EnemyConfig {
@@ -13,6 +20,8 @@ pub struct EnemyConfig {
     pub enemy_player_min_distance: f32,
     pub enemy_player_max_distance: f32,
     pub enemy_enemy_min_distance: f32,
+    pub patrol_count: i32,
+    pub patrol_stops: usize,
 }
 
 impl Default for EnemyConfig {
@@ -22,14 +31,25 @@ impl Default for EnemyConfig {
             enemy_player_min_distance: 10.0,
             enemy_player_max_distance: 30.0,
             enemy_enemy_min_distance: 5.0,
+            patrol_count: 2,
+            patrol_stops: 3,
         }
     }
 }
 
+/// Where and how enemies spawn: the tile grid, the level being played and the spawn RNG.
+#[derive(SystemParam)]
+pub struct EnemySpawnContext<'w> {
+    tile_registry: Res<'w, TileRegistry>,
+    level_assets: Res<'w, LevelAssets>,
+    levels: Res<'w, Assets<Level>>,
+    game_rng: ResMut<'w, GameRng>,
+}
+
 pub fn init_enemy(
     mut commands: Commands,
     mut tile_registry_created_events: MessageReader<PlayerStartupTileSelectedEvent>,
-    tile_registry: Res<TileRegistry>,
+    mut spawn_context: EnemySpawnContext,
     tiles_query: Query<(Entity, &Tile, &Transform)>,
     enemy_spawned_query: Query<&EnemySpawned>,
     player_spawned_query: Query<&PlayerWithAssetsSpawned>,
@@ -42,88 +62,150 @@ pub fn init_enemy(
         return;
     }
 
+    let Some(level) = spawn_context.level_assets.current(&spawn_context.levels) else {
+        return;
+    };
+    let tile_registry = &spawn_context.tile_registry;
     let enemy_config = EnemyConfig::default();
+    let rng = spawn_context.game_rng.stream(RngStream::Spawning);
     for event in tile_registry_created_events.read() {
         info!("TileRegistry created, initializing enemy system");
 
         if let Ok((_, tile, _)) = tiles_query.get(event.tile_entity) {
             let mut enemies = Vec::new();
-            for _ in 0..enemy_config.enemy_count {
+
+            // Spawn markers of stamped prefabs are used first, random tiles fill up the rest
+            let markers =
+                level.spawn_markers.iter().filter(|(_, marker)| *marker == SpawnMarker::Enemy);
+            for &(coord, _) in markers.take(enemy_config.enemy_count as usize) {
+                let Some(Ok((entity, _, transform))) =
+                    tile_registry.grid.get(coord).map(|entity| tiles_query.get(entity))
+                else {
+                    continue;
+                };
+                info!("Spawning enemy at prefab marker {:?}", coord);
+                enemies.push(coord);
+                spawn_enemy(&mut commands, entity, transform.translation);
+            }
+
+            for _ in enemies.len()..enemy_config.enemy_count as usize {
                 if let Some(enemy) = find_spawn_position(
-                    &tile_registry.tiles_by_coord,
+                    rng,
+                    &tile_registry.grid,
+                    level,
                     (tile.x, tile.z),
                     enemies.as_slice(),
-                    enemy_config.enemy_player_min_distance,
-                    enemy_config.enemy_player_max_distance,
-                    enemy_config.enemy_enemy_min_distance,
+                    &enemy_config,
                 ) {
                     info!("Spawning enemy at {:?}", enemy);
                     enemies.push(enemy.0);
 
                     if let Ok((entity, _, transform)) = tiles_query.get(enemy.1) {
                         info!("Spawning enemy at {:?}", transform.translation);
-                        commands.spawn((
-                            Enemy::default(),
-                            EnemyGizmo::default(),
-                            Transform::from_translation(transform.translation),
-                            TilePosition::for_entity(entity),
-                            Movement::default(),
-                            MovementSpeed::enemy(),
-                            MovementState::Walking,
-                            EnemyLastMovementTime::default(),
-                            CharacterType::Enemy,
-                        ));
+                        spawn_enemy(&mut commands, entity, transform.translation);
                     }
                 }
             }
+
+            // Guards walk a route of their own instead of chasing the player
+            for patrol_index in 0..enemy_config.patrol_count {
+                let mut route = Vec::new();
+                for _ in 0..enemy_config.patrol_stops {
+                    if let Some(stop) = find_spawn_position(
+                        rng,
+                        &tile_registry.grid,
+                        level,
+                        (tile.x, tile.z),
+                        enemies.as_slice(),
+                        &enemy_config,
+                    ) {
+                        enemies.push(stop.0);
+                        route.push(stop.1);
+                    }
+                }
+                let Some(Ok((entity, _, transform))) =
+                    route.first().map(|&first_stop| tiles_query.get(first_stop))
+                else {
+                    continue;
+                };
+                let mode =
+                    if patrol_index % 2 == 0 { PatrolMode::Loop } else { PatrolMode::PingPong };
+                info!("Spawning {:?} patrol with {} stops", mode, route.len());
+                spawn_enemy(&mut commands, entity, transform.translation)
+                    .insert((Patrol::new(route, mode), EnemyGizmo::patrol()));
+            }
             commands.spawn(EnemySpawned);
         }
     }
     info!("Enemy system initialized");
 }
 
+fn spawn_enemy<'a>(
+    commands: &'a mut Commands,
+    tile: Entity,
+    position: Vec3,
+) -> EntityCommands<'a> {
+    commands.spawn((
+        Enemy::default(),
+        EnemyGizmo::default(),
+        Transform::from_translation(position),
+        TransformInterpolation::default(),
+        TilePosition::for_entity(tile),
+        Movement::default(),
+        MovementSpeed::enemy(),
+        Steering::default(),
+        MovementState::Walking,
+        CharacterType::Enemy,
+    ))
+}
+
 /// Znajduje losową wolną pozycję na planszy w określonym zakresie odległości
-/// od gracza i z minimalną odległością od wrogów.
+/// od gracza i z minimalną odległością od wrogów, w strefach spawnu poziomu.
 ///
 /// # Arguments
-/// * `tiles_by_coord` - Mapa wszystkich kafelków na planszy
+/// * `rng` - Generator losowania, zwykle strumień `RngStream::Spawning`
+/// * `tiles` - Siatka wszystkich kafelków na planszy
+/// * `level` - Poziom ze strefami spawnu wrogów
 /// * `player_tile_pos` - Pozycja gracza
 /// * `enemy_tile_positions` - Wektor pozycji wrogów
-/// * `min_distance_from_player` - Minimalna odległość od gracza (w kafelkach)
-/// * `max_distance_from_player` - Maksymalna odległość od gracza (w kafelkach)
-/// * `min_distance_from_enemies` - Minimalna odległość od każdego wroga (w kafelkach)
+/// * `config` - Minimalne i maksymalne odległości (w kafelkach)
 ///
 /// # Returns
 /// `Option<(IVec2, TileData)>` - Znaleziony kafelek lub None jeśli nie znaleziono
 pub fn find_spawn_position(
-    tiles_by_coord: &HashMap<(i32, i32), Entity>,
+    rng: &mut impl Rng,
+    tiles: &TileGrid,
+    level: &Level,
     player_tile_pos: (i32, i32),
     enemy_tile_positions: &[(i32, i32)],
-    min_distance_from_player: f32,
-    max_distance_from_player: f32,
-    min_distance_from_enemies: f32,
+    config: &EnemyConfig,
 ) -> Option<((i32, i32), Entity)> {
-    let mut rng = rand::rng();
     let player_vec = Vec2::new(player_tile_pos.0 as f32, player_tile_pos.1 as f32);
 
     // Prefiltruj najpierw odległość od gracza (najbardziej ograniczający warunek)
-    let valid_tiles: Vec<_> = tiles_by_coord
+    let valid_tiles: Vec<_> = tiles
         .iter()
         .filter(|(coord, _)| {
             let coord_vec = Vec2::new(coord.0 as f32, coord.1 as f32);
             let distance_to_player = coord_vec.distance(player_vec);
 
             // Szybkie odrzucenie: sprawdź zakres od gracza
-            if distance_to_player < min_distance_from_player
-                || distance_to_player > max_distance_from_player
+            if distance_to_player < config.enemy_player_min_distance
+                || distance_to_player > config.enemy_player_max_distance
             {
                 return false;
             }
 
+            // Tylko kafelki, po których da się chodzić, w strefach spawnu
+            let walkable = tiles.index_of(*coord).is_some_and(|index| tiles.is_walkable(index));
+            if !walkable || !level.allows_enemy_spawn(*coord) {
+                return false;
+            }
+
             // Sprawdź odległość od każdego wroga
             enemy_tile_positions.iter().all(|enemy_pos| {
                 let enemy_vec = Vec2::new(enemy_pos.0 as f32, enemy_pos.1 as f32);
-                coord_vec.distance(enemy_vec) >= min_distance_from_enemies
+                coord_vec.distance(enemy_vec) >= config.enemy_enemy_min_distance
             })
         })
         .collect();
@@ -133,7 +215,30 @@ pub fn find_spawn_position(
         None
     } else {
         let index = rng.random_range(0..valid_tiles.len());
-        valid_tiles.get(index).map(|(coord, tile)| (**coord, **tile))
+        valid_tiles.get(index).copied()
+    }
+}
+
+/// Despawns enemies standing on chunks that streamed out, and patrols whose route left
+/// the loaded world.
+pub fn despawn_unloaded_enemies(
+    mut commands: Commands,
+    mut streamed_events: MessageReader<ChunksStreamedEvent>,
+    tile_registry: Res<TileRegistry>,
+    enemies: Query<(Entity, &TilePosition, Option<&Patrol>), With<Enemy>>,
+) {
+    if streamed_events.read().filter(|event| !event.unloaded.is_empty()).count() == 0 {
+        return;
+    }
+
+    let loaded = |tile: &Entity| tile_registry.grid.index_of_entity(*tile).is_some();
+    for (entity, tile_position, patrol) in enemies.iter() {
+        let on_loaded_tile = tile_position.tile.as_ref().is_some_and(loaded);
+        let route_loaded = patrol.is_none_or(|patrol| patrol.route.iter().all(loaded));
+        if !on_loaded_tile || !route_loaded {
+            debug!("Despawning enemy {:?} on an unloaded chunk", entity);
+            commands.entity(entity).despawn();
+        }
     }
 }
 
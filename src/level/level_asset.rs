@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::TerrainType;
+use crate::level::prefab::{PlacedProp, PrefabPlacement, SpawnMarker};
+
+/// A level as described by a `.level.ron` file.
+///
+/// Tile coordinates are global, like `Tile.x`/`Tile.z`: `(0, 0)` is the first tile of the
+/// first chunk and the level spans `chunk_cols * tiles_per_chunk` tiles along x.
+#[derive(Asset, TypePath, Deserialize, Serialize, Clone, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Level {
+    pub chunk_cols: i32,
+    pub chunk_rows: i32,
+    pub tiles_per_chunk: i32, // Tiles per chunk side
+    pub tile_size: f32,       // World units per tile side
+    /// Symbols used in `tiles`
+    #[serde(default)]
+    pub legend: BTreeMap<char, LevelTile>,
+    /// One string per row of tiles, first row at z = 0. Empty means plain ground everywhere
+    #[serde(default)]
+    pub tiles: Vec<String>,
+    /// Defaults to the middle of the level
+    #[serde(default)]
+    pub player_spawn: Option<(i32, i32)>,
+    /// Enemies spawn anywhere when empty
+    #[serde(default)]
+    pub enemy_spawn_zones: Vec<SpawnZone>,
+    /// Height of the rolling hills laid over every tile's own elevation, in world units
+    #[serde(default)]
+    pub hills: f32,
+    /// Stamped over `tiles` while the level loads
+    #[serde(default)]
+    pub prefabs: Vec<PrefabPlacement>,
+    /// Placed in the editor or left behind by stamped prefabs
+    #[serde(default)]
+    pub props: Vec<PlacedProp>,
+    #[serde(default)]
+    pub spawn_markers: Vec<((i32, i32), SpawnMarker)>,
+    /// Tiles covered by stamped prefabs
+    #[serde(skip)]
+    pub stamped: HashSet<(i32, i32)>,
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(path = "levels/wasteland.level.ron")]
+    pub level: Handle<Level>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LevelTile {
+    /// Name of a type in the tile types file
+    #[serde(default)]
+    pub terrain: TerrainType,
+    /// `false` blocks the tile whatever its type; a type that cannot be walked blocks it too
+    #[serde(default = "walkable_by_default")]
+    pub walkable: bool,
+    /// Height of the tile above the ground plane, in world units
+    #[serde(default)]
+    pub elevation: f32,
+}
+
+/// Inclusive rectangle of tiles enemies may spawn on.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnZone {
+    pub min: (i32, i32),
+    pub max: (i32, i32),
+}
+
+/// Layout problems found after the file itself parsed.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum LevelLayoutError {
+    #[error("level needs at least one chunk and one tile per chunk")]
+    Empty,
+    #[error("tile_size * tiles_per_chunk must be a whole number of world units, got {0}")]
+    FractionalChunkSize(f32),
+    #[error("`tiles` has {found} rows, expected {expected}")]
+    RowCount { expected: usize, found: usize },
+    #[error("`tiles` row {row} is {found} tiles wide, expected {expected}")]
+    RowWidth { row: usize, expected: usize, found: usize },
+    #[error("`tiles` row {row}, column {column}: symbol {symbol:?} is not in the legend")]
+    UnknownSymbol { row: usize, column: usize, symbol: char },
+    #[error("player spawn {0:?} is outside the level")]
+    SpawnOutsideLevel((i32, i32)),
+    #[error("player spawn {0:?} is not walkable")]
+    SpawnNotWalkable((i32, i32)),
+    #[error("enemy spawn zone {index} ({min:?} to {max:?}) does not overlap the level")]
+    ZoneOutsideLevel { index: usize, min: (i32, i32), max: (i32, i32) },
+}
+
+fn walkable_by_default() -> bool {
+    true
+}
+
+impl Default for LevelTile {
+    fn default() -> Self {
+        Self { terrain: TerrainType::default(), walkable: true, elevation: 0.0 }
+    }
+}
+
+impl LevelAssets {
+    pub fn current<'a>(&self, levels: &'a Assets<Level>) -> Option<&'a Level> {
+        levels.get(&self.level)
+    }
+}
+
+impl SpawnZone {
+    pub fn contains(&self, (x, z): (i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&x) && (self.min.1..=self.max.1).contains(&z)
+    }
+}
+
+impl Level {
+    /// Level size in tiles along x and z.
+    pub fn size(&self) -> (i32, i32) {
+        (self.chunk_cols * self.tiles_per_chunk, self.chunk_rows * self.tiles_per_chunk)
+    }
+
+    /// World units per chunk side.
+    pub fn chunk_size(&self) -> i32 {
+        (self.tile_size * self.tiles_per_chunk as f32).round() as i32
+    }
+
+    /// Chunk holding a global tile coordinate; may lie outside the level.
+    pub fn chunk_of(&self, (x, z): (i32, i32)) -> (i32, i32) {
+        (x.div_euclid(self.tiles_per_chunk), z.div_euclid(self.tiles_per_chunk))
+    }
+
+    /// Chunk under a world position. The level's chunks are centred on the world origin.
+    pub fn chunk_at(&self, position: Vec3) -> (i32, i32) {
+        let size = self.chunk_size() as f32;
+        (
+            ((position.x + self.chunk_cols as f32 * size / 2.0) / size).floor() as i32,
+            ((position.z + self.chunk_rows as f32 * size / 2.0) / size).floor() as i32,
+        )
+    }
+
+    /// World position in tile units, with tile centres on whole numbers, as taken by
+    /// `TileGrid::height_at`.
+    pub fn tile_space(&self, position: Vec3) -> Vec2 {
+        let chunk_size = self.chunk_size() as f32;
+        let tile_size = chunk_size / self.tiles_per_chunk as f32;
+        Vec2::new(
+            (position.x + self.chunk_cols as f32 * chunk_size / 2.0) / tile_size - 0.5,
+            (position.z + self.chunk_rows as f32 * chunk_size / 2.0) / tile_size - 0.5,
+        )
+    }
+
+    pub fn contains(&self, (x, z): (i32, i32)) -> bool {
+        let (width, depth) = self.size();
+        (0..width).contains(&x) && (0..depth).contains(&z)
+    }
+
+    pub fn tile(&self, (x, z): (i32, i32)) -> LevelTile {
+        self.tiles
+            .get(z as usize)
+            .and_then(|row| row.chars().nth(x as usize))
+            .and_then(|symbol| self.legend.get(&symbol))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Legend symbol for `tile`, added to the legend if no symbol describes it yet.
+    pub fn symbol_for(&mut self, tile: LevelTile) -> char {
+        if let Some((&symbol, _)) = self.legend.iter().find(|&(_, &entry)| entry == tile) {
+            return symbol;
+        }
+        let symbol = ('!'..='~')
+            .find(|symbol| !self.legend.contains_key(symbol))
+            .expect("legend has a symbol for every terrain and walkability");
+        self.legend.insert(symbol, tile);
+        symbol
+    }
+
+    /// Sets the tile at `coord` to a legend symbol, spelling out the map first if it was empty.
+    pub fn set_symbol(&mut self, (x, z): (i32, i32), symbol: char) {
+        if self.tiles.is_empty() {
+            let (width, depth) = self.size();
+            let ground = self.symbol_for(LevelTile::default());
+            let row: String = std::iter::repeat_n(ground, width as usize).collect();
+            self.tiles = vec![row; depth as usize];
+        }
+        if let Some(row) = self.tiles.get_mut(z as usize) {
+            *row = row
+                .chars()
+                .enumerate()
+                .map(|(column, old)| if column == x as usize { symbol } else { old })
+                .collect();
+        }
+    }
+
+    /// Replaces the tile at `coord`, adding a legend symbol for it if there is none yet.
+    pub fn set_tile(&mut self, coord: (i32, i32), tile: LevelTile) {
+        let symbol = self.symbol_for(tile);
+        self.set_symbol(coord, symbol);
+    }
+
+    /// The level as the contents of a `.level.ron` file.
+    ///
+    /// Stamped prefabs are written out as the tiles, props and markers they left and their
+    /// placements dropped, so loading the file does not stamp them a second time. Legend
+    /// symbols no tile uses any more are dropped as well.
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        let mut level = Level { prefabs: Vec::new(), ..self.clone() };
+        if !level.tiles.is_empty() {
+            let used: HashSet<char> = level.tiles.iter().flat_map(|row| row.chars()).collect();
+            level.legend.retain(|symbol, _| used.contains(symbol));
+        }
+        ron::ser::to_string_pretty(&level, ron::ser::PrettyConfig::default())
+    }
+
+    /// Walkable regions, 4-connected so diagonal squeezes don't count as a connection.
+    pub fn walkable_regions(&self) -> Vec<Vec<(i32, i32)>> {
+        let (width, depth) = self.size();
+        let walkable: Vec<bool> = (0..depth)
+            .flat_map(|z| (0..width).map(move |x| (x, z)))
+            .map(|coord| self.tile(coord).walkable)
+            .collect();
+        let index = |(x, z): (i32, i32)| (z * width + x) as usize;
+
+        let mut visited = vec![false; walkable.len()];
+        let mut regions = Vec::new();
+        for start in (0..depth).flat_map(|z| (0..width).map(move |x| (x, z))) {
+            if visited[index(start)] || !walkable[index(start)] {
+                continue;
+            }
+            visited[index(start)] = true;
+            let mut region = Vec::new();
+            let mut open = VecDeque::from([start]);
+            while let Some((x, z)) = open.pop_front() {
+                region.push((x, z));
+                for next in [(x, z + 1), (x + 1, z), (x, z - 1), (x - 1, z)] {
+                    if self.contains(next) && !visited[index(next)] && walkable[index(next)] {
+                        visited[index(next)] = true;
+                        open.push_back(next);
+                    }
+                }
+            }
+            regions.push(region);
+        }
+        regions
+    }
+
+    pub fn player_spawn(&self) -> (i32, i32) {
+        let (width, depth) = self.size();
+        self.player_spawn.unwrap_or((width / 2, depth / 2))
+    }
+
+    pub fn allows_enemy_spawn(&self, coord: (i32, i32)) -> bool {
+        self.enemy_spawn_zones.is_empty()
+            || self.enemy_spawn_zones.iter().any(|zone| zone.contains(coord))
+    }
+
+    pub fn validate(&self) -> Result<(), LevelLayoutError> {
+        if self.chunk_cols <= 0 || self.chunk_rows <= 0 || self.tiles_per_chunk <= 0 {
+            return Err(LevelLayoutError::Empty);
+        }
+        let chunk_size = self.tile_size * self.tiles_per_chunk as f32;
+        if chunk_size <= 0.0 || chunk_size.fract() != 0.0 {
+            return Err(LevelLayoutError::FractionalChunkSize(chunk_size));
+        }
+
+        let (width, depth) = self.size();
+        if !self.tiles.is_empty() {
+            if self.tiles.len() != depth as usize {
+                return Err(LevelLayoutError::RowCount {
+                    expected: depth as usize,
+                    found: self.tiles.len(),
+                });
+            }
+            for (row, symbols) in self.tiles.iter().enumerate() {
+                let found = symbols.chars().count();
+                if found != width as usize {
+                    return Err(LevelLayoutError::RowWidth {
+                        row,
+                        expected: width as usize,
+                        found,
+                    });
+                }
+                if let Some((column, symbol)) = symbols
+                    .chars()
+                    .enumerate()
+                    .find(|(_, symbol)| !self.legend.contains_key(symbol))
+                {
+                    return Err(LevelLayoutError::UnknownSymbol { row, column, symbol });
+                }
+            }
+        }
+
+        let spawn = self.player_spawn();
+        if !self.contains(spawn) {
+            return Err(LevelLayoutError::SpawnOutsideLevel(spawn));
+        }
+        if !self.tile(spawn).walkable {
+            return Err(LevelLayoutError::SpawnNotWalkable(spawn));
+        }
+        for (index, zone) in self.enemy_spawn_zones.iter().enumerate() {
+            if zone.max.0 < 0 || zone.max.1 < 0 || zone.min.0 >= width || zone.min.1 >= depth {
+                return Err(LevelLayoutError::ZoneOutsideLevel {
+                    index,
+                    min: zone.min,
+                    max: zone.max,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALL: TerrainType = TerrainType::named("ConcreteWall");
+
+    fn level(tiles: &[&str]) -> Level {
+        Level {
+            chunk_cols: 1,
+            chunk_rows: 1,
+            tiles_per_chunk: 3,
+            tile_size: 2.0,
+            legend: BTreeMap::from([
+                ('.', LevelTile::default()),
+                ('#', LevelTile { terrain: WALL, walkable: false, elevation: 1.0 }),
+            ]),
+            tiles: tiles.iter().map(|row| row.to_string()).collect(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn reads_tiles_from_the_map() {
+        let level = level(&["...", "..#", "..."]);
+        assert_eq!(level.validate(), Ok(()));
+        assert!(!level.tile((2, 1)).walkable);
+        assert_eq!(level.tile((2, 1)).terrain, WALL);
+        assert!(level.tile((0, 0)).walkable);
+    }
+
+    #[test]
+    fn saves_edits_as_level_ron() {
+        let mut level = level(&["...", "..#", "..."]);
+        level.set_tile((2, 1), LevelTile::default());
+        level.set_tile((0, 0), LevelTile { terrain: WALL, ..default() });
+        level.props.push(PlacedProp { coord: (1, 1), name: "fuel_pump".to_string() });
+
+        let saved: Level = ron::de::from_str(&level.to_ron().unwrap()).unwrap();
+        assert_eq!(saved.tiles, level.tiles);
+        assert_eq!(saved.tile((0, 0)).terrain, WALL);
+        assert!(saved.tile((2, 1)).walkable);
+        assert_eq!(saved.props, level.props);
+        // The blocked wall symbol is no longer used
+        assert_eq!(saved.legend.len(), 2);
+    }
+
+    #[test]
+    fn finds_chunks_beyond_the_level() {
+        let level = Level { chunk_cols: 3, chunk_rows: 3, ..level(&[]) };
+        // Chunks are 6 world units wide and the middle one is centred on the origin
+        assert_eq!(level.chunk_at(Vec3::ZERO), (1, 1));
+        assert_eq!(level.chunk_at(Vec3::new(-9.5, 0.0, 9.5)), (-1, 3));
+        assert_eq!(level.chunk_of((-1, 9)), (-1, 3));
+        // Tile (4, 4) is the middle one, its centre on the origin
+        assert_eq!(level.tile_space(Vec3::new(1.0, 0.0, -2.0)), Vec2::new(4.5, 3.0));
+    }
+
+    #[test]
+    fn rejects_malformed_maps() {
+        assert_eq!(
+            level(&["...", "..", "..."]).validate(),
+            Err(LevelLayoutError::RowWidth { row: 1, expected: 3, found: 2 })
+        );
+        assert_eq!(
+            level(&["...", ".x.", "..."]).validate(),
+            Err(LevelLayoutError::UnknownSymbol { row: 1, column: 1, symbol: 'x' })
+        );
+        assert_eq!(
+            level(&["...", ".#.", "..."]).validate(),
+            Err(LevelLayoutError::SpawnNotWalkable((1, 1)))
+        );
+    }
+}
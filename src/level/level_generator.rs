@@ -0,0 +1,412 @@
+use std::collections::{BTreeMap, HashSet};
+
+use bevy::asset::AssetPath;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::components::TerrainType;
+use crate::level::level_asset::{Level, LevelAssets, LevelTile};
+use crate::level::prefab::{OverlapRule, Prefab, PrefabAssets, PrefabPlacement, PrefabRotation};
+use crate::shared::{GameRng, RngStream};
+
+const GROUND: char = '.';
+const ROAD: char = '=';
+const SAND: char = ':';
+const RUBBLE: char = '%';
+const WALL: char = '#';
+const WATER: char = '~';
+const SWAMP: char = '&';
+
+/// Tile type painted for each symbol; all of them must be in the shipped tile types
+const SYMBOL_TERRAINS: [(char, TerrainType); 7] = [
+    (GROUND, TerrainType::GROUND),
+    (ROAD, TerrainType::named("Asphalt")),
+    (SAND, TerrainType::named("Sand")),
+    (RUBBLE, TerrainType::named("Rubble")),
+    (WALL, TerrainType::named("ConcreteWall")),
+    (WATER, TerrainType::named("ShallowWater")),
+    (SWAMP, TerrainType::named("ToxicSludge")),
+];
+
+/// How far impassable rock rises above the ground, in world units
+const WALL_HEIGHT: f32 = 1.5;
+
+/// Random positions tried per landmark before giving up on it
+const LANDMARK_ATTEMPTS: usize = 20;
+
+/// Size and feature counts for a generated map. Pass `--procedural` to play one.
+#[derive(Resource, Clone, Debug)]
+pub struct MapGenSettings {
+    pub chunk_cols: i32,
+    pub chunk_rows: i32,
+    pub tiles_per_chunk: i32,
+    pub tile_size: f32,
+    pub obstacle_threshold: f32, // Noise value above which tiles become impassable
+    pub clearings: usize,
+    pub clearing_radius: i32,
+    pub ruins: usize,
+    pub landmarks: usize, // Prefabs stamped into the map
+    pub hills: f32,       // See `Level::hills`
+}
+
+impl Default for MapGenSettings {
+    fn default() -> Self {
+        Self {
+            chunk_cols: 3,
+            chunk_rows: 3,
+            tiles_per_chunk: 15,
+            tile_size: 2.0,
+            obstacle_threshold: 0.62,
+            clearings: 5,
+            clearing_radius: 4,
+            ruins: 4,
+            landmarks: 2,
+            hills: 2.5,
+        }
+    }
+}
+
+/// Tile symbols of the map being generated, row by row from z = 0.
+struct SymbolGrid {
+    width: i32,
+    depth: i32,
+    symbols: Vec<char>,
+}
+
+impl SymbolGrid {
+    fn contains(&self, (x, z): (i32, i32)) -> bool {
+        (0..self.width).contains(&x) && (0..self.depth).contains(&z)
+    }
+
+    fn index(&self, (x, z): (i32, i32)) -> usize {
+        (z * self.width + x) as usize
+    }
+
+    fn get(&self, coord: (i32, i32)) -> char {
+        self.symbols[self.index(coord)]
+    }
+
+    fn set(&mut self, coord: (i32, i32), symbol: char) {
+        if self.contains(coord) {
+            let index = self.index(coord);
+            self.symbols[index] = symbol;
+        }
+    }
+
+    fn coords(&self) -> impl Iterator<Item = (i32, i32)> + use<> {
+        let (width, depth) = (self.width, self.depth);
+        (0..depth).flat_map(move |z| (0..width).map(move |x| (x, z)))
+    }
+}
+
+/// Generates a wasteland level from `seed`: noise-based obstacle fields and terrain, ruins,
+/// clearings linked by roads, landmarks stamped from `prefabs` (each with its asset path), and
+/// a single connected walkable area with the player spawn in it. The same seed, settings and
+/// prefabs always give the same level.
+pub fn generate_level(
+    seed: u64,
+    settings: &MapGenSettings,
+    prefabs: &[(&AssetPath, &Prefab)],
+) -> Level {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let width = settings.chunk_cols * settings.tiles_per_chunk;
+    let depth = settings.chunk_rows * settings.tiles_per_chunk;
+    let mut grid = SymbolGrid { width, depth, symbols: vec![GROUND; (width * depth) as usize] };
+
+    // 1. Obstacle field with rubble fringes, plus patches of sand, water and swamp
+    for coord in grid.coords() {
+        grid.set(coord, noise_symbol(seed, coord, settings.obstacle_threshold));
+    }
+
+    // 2. Ruins: walled rectangles with a doorway and rubble inside
+    for _ in 0..settings.ruins {
+        let (ruin_width, ruin_depth) = (rng.random_range(5..10), rng.random_range(5..10));
+        let (left, bottom) = (
+            rng.random_range(0..(width - ruin_width).max(1)),
+            rng.random_range(0..(depth - ruin_depth).max(1)),
+        );
+        let (right, top) = (left + ruin_width - 1, bottom + ruin_depth - 1);
+        for x in left..=right {
+            for z in bottom..=top {
+                let on_wall = x == left || x == right || z == bottom || z == top;
+                let symbol = if on_wall {
+                    WALL
+                } else if rng.random_bool(0.2) {
+                    RUBBLE
+                } else {
+                    GROUND
+                };
+                grid.set((x, z), symbol);
+            }
+        }
+        let door = match rng.random_range(0..4) {
+            0 => (rng.random_range(left + 1..right), bottom),
+            1 => (rng.random_range(left + 1..right), top),
+            2 => (left, rng.random_range(bottom + 1..top)),
+            _ => (right, rng.random_range(bottom + 1..top)),
+        };
+        grid.set(door, RUBBLE);
+    }
+
+    // 3. Clearings, the first one in the middle for the player
+    let margin = settings.clearing_radius;
+    let mut clearings = vec![(width / 2, depth / 2)];
+    while clearings.len() < settings.clearings.max(1) {
+        clearings.push((
+            rng.random_range(margin..(width - margin).max(margin + 1)),
+            rng.random_range(margin..(depth - margin).max(margin + 1)),
+        ));
+    }
+    let radius = settings.clearing_radius;
+    for &(center_x, center_z) in &clearings {
+        for x in center_x - radius..=center_x + radius {
+            for z in center_z - radius..=center_z + radius {
+                let inside = (x - center_x).pow(2) + (z - center_z).pow(2) <= radius * radius;
+                if inside && grid.contains((x, z)) && matches!(grid.get((x, z)), WALL | RUBBLE) {
+                    grid.set((x, z), GROUND);
+                }
+            }
+        }
+    }
+
+    // 4. Winding roads from each clearing to the next
+    for pair in clearings.windows(2) {
+        let (mut x, mut z) = pair[0];
+        let (target_x, target_z) = pair[1];
+        grid.set((x, z), ROAD);
+        while (x, z) != (target_x, target_z) {
+            let (dx, dz) = (target_x - x, target_z - z);
+            let along_x =
+                dz == 0 || (dx != 0 && rng.random_range(0..dx.abs() + dz.abs()) < dx.abs());
+            if along_x {
+                x += dx.signum();
+            } else {
+                z += dz.signum();
+            }
+            grid.set((x, z), ROAD);
+        }
+    }
+
+    let tiles = (0..depth).map(|z| (0..width).map(|x| grid.get((x, z))).collect()).collect();
+    let mut level = Level {
+        chunk_cols: settings.chunk_cols,
+        chunk_rows: settings.chunk_rows,
+        tiles_per_chunk: settings.tiles_per_chunk,
+        tile_size: settings.tile_size,
+        legend: generated_legend(),
+        tiles,
+        hills: settings.hills,
+        ..default()
+    };
+
+    // 5. Landmarks, kept clear of the player's clearing
+    let center = (width / 2, depth / 2);
+    let landmarks = if prefabs.is_empty() { 0 } else { settings.landmarks };
+    for _ in 0..landmarks {
+        let (path, prefab) = prefabs[rng.random_range(0..prefabs.len())];
+        let rotation =
+            [PrefabRotation::R0, PrefabRotation::R90, PrefabRotation::R180, PrefabRotation::R270]
+                [rng.random_range(0..4)];
+        let mut placement = PrefabPlacement {
+            prefab: path.to_string(),
+            at: (0, 0),
+            rotation,
+            mirrored: rng.random_bool(0.5),
+            overlap: OverlapRule::Replace,
+        };
+        let (footprint_x, footprint_z) = placement.footprint(prefab);
+        for _ in 0..LANDMARK_ATTEMPTS {
+            placement.at = (
+                rng.random_range(0..(width - footprint_x + 1).max(1)),
+                rng.random_range(0..(depth - footprint_z + 1).max(1)),
+            );
+            let nearest_x = center.0.clamp(placement.at.0, placement.at.0 + footprint_x - 1);
+            let nearest_z = center.1.clamp(placement.at.1, placement.at.1 + footprint_z - 1);
+            let clear_of_spawn =
+                (nearest_x - center.0).pow(2) + (nearest_z - center.1).pow(2) > (radius + 1).pow(2);
+            if clear_of_spawn && level.stamp(prefab, &placement).is_ok() {
+                break;
+            }
+        }
+    }
+
+    // 6. Keep only the largest walkable region so every tile can reach every other
+    let mut regions = level.walkable_regions();
+    regions.sort_by_key(|region| std::cmp::Reverse(region.len()));
+    let wall = level.symbol_for(generated_tile(WALL));
+    for &coord in regions.iter().skip(1).flatten() {
+        level.set_symbol(coord, wall);
+    }
+    let reachable: HashSet<(i32, i32)> = regions.first().into_iter().flatten().copied().collect();
+    level.spawn_markers.retain(|(coord, _)| reachable.contains(coord));
+    level.player_spawn = reachable
+        .iter()
+        .copied()
+        .min_by_key(|&(x, z)| ((x - center.0).pow(2) + (z - center.1).pow(2), x, z));
+    level
+}
+
+/// Replaces the loaded level with a generated one, seeded from the game seed.
+pub fn generate_procedural_level(
+    settings: Res<MapGenSettings>,
+    mut game_rng: ResMut<GameRng>,
+    mut level_assets: ResMut<LevelAssets>,
+    mut levels: ResMut<Assets<Level>>,
+    prefab_assets: Res<PrefabAssets>,
+    prefabs: Res<Assets<Prefab>>,
+) {
+    let seed = game_rng.stream(RngStream::MapGeneration).random();
+    let prefabs: Vec<(&AssetPath, &Prefab)> = prefab_assets
+        .prefabs
+        .iter()
+        .filter_map(|handle| Some((handle.path()?, prefabs.get(handle)?)))
+        .collect();
+    let level = generate_level(seed, &settings, &prefabs);
+    info!("Generated {}x{} tile level from map seed {}", level.size().0, level.size().1, seed);
+    level_assets.level = levels.add(level);
+}
+
+/// Open wasteland beyond the edge of the level, so streamed chunks never run out of world.
+/// Uses the same obstacle and terrain noise as the first step of `generate_level`.
+pub fn wilderness_tile(seed: u64, coord: (i32, i32)) -> LevelTile {
+    generated_tile(noise_symbol(seed, coord, MapGenSettings::default().obstacle_threshold))
+}
+
+/// Tile at a global coordinate: taken from `level` inside it, wilderness all around it,
+/// with the level's rolling hills on top.
+pub fn world_tile(level: &Level, seed: u64, coord: (i32, i32)) -> LevelTile {
+    let mut tile =
+        if level.contains(coord) { level.tile(coord) } else { wilderness_tile(seed, coord) };
+    let (x, z) = (coord.0 as f32 / 13.0, coord.1 as f32 / 13.0);
+    tile.elevation += level.hills * fractal_noise(seed.wrapping_add(2), x, z);
+    tile
+}
+
+/// Obstacle field with rubble fringes, plus patches of sand, water and swamp.
+fn noise_symbol(seed: u64, (x, z): (i32, i32), obstacle_threshold: f32) -> char {
+    let obstacle = fractal_noise(seed, x as f32 / 7.0, z as f32 / 7.0);
+    let terrain = fractal_noise(seed.wrapping_add(1), x as f32 / 11.0, z as f32 / 11.0);
+    if obstacle > obstacle_threshold {
+        WALL
+    } else if obstacle > obstacle_threshold - 0.05 {
+        RUBBLE
+    } else if terrain > 0.64 {
+        SAND
+    } else if terrain < 0.3 {
+        SWAMP
+    } else if terrain < 0.36 {
+        WATER
+    } else {
+        GROUND
+    }
+}
+
+fn generated_tile(symbol: char) -> LevelTile {
+    let terrain = SYMBOL_TERRAINS
+        .iter()
+        .find(|&&(painted, _)| painted == symbol)
+        .map_or(TerrainType::GROUND, |&(_, terrain)| terrain);
+    let walkable = symbol != WALL;
+    LevelTile { terrain, walkable, elevation: if walkable { 0.0 } else { WALL_HEIGHT } }
+}
+
+fn generated_legend() -> BTreeMap<char, LevelTile> {
+    SYMBOL_TERRAINS.iter().map(|&(symbol, _)| (symbol, generated_tile(symbol))).collect()
+}
+
+/// Pseudo-random value in [0, 1) for a lattice point.
+fn lattice_value(seed: u64, x: i32, z: i32) -> f32 {
+    let mut hash = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    ((hash ^ (hash >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+}
+
+/// Smoothly interpolated value noise in [0, 1).
+fn value_noise(seed: u64, x: f32, z: f32) -> f32 {
+    let (cell_x, cell_z) = (x.floor() as i32, z.floor() as i32);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, tz) = (smooth(x - cell_x as f32), smooth(z - cell_z as f32));
+    let bottom = lattice_value(seed, cell_x, cell_z)
+        + (lattice_value(seed, cell_x + 1, cell_z) - lattice_value(seed, cell_x, cell_z)) * tx;
+    let top = lattice_value(seed, cell_x, cell_z + 1)
+        + (lattice_value(seed, cell_x + 1, cell_z + 1) - lattice_value(seed, cell_x, cell_z + 1))
+            * tx;
+    bottom + (top - bottom) * tz
+}
+
+/// Three octaves of value noise, normalised back to [0, 1).
+fn fractal_noise(seed: u64, x: f32, z: f32) -> f32 {
+    let mut total = 0.0;
+    let mut weight = 1.0;
+    let mut scale = 1.0;
+    for octave in 0..3 {
+        total += value_noise(seed.wrapping_add(octave), x * scale, z * scale) * weight;
+        weight *= 0.5;
+        scale *= 2.0;
+    }
+    total / 1.75
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::prefab::PrefabCell;
+    use crate::level::tile_types::TileTypes;
+
+    const BUNKER_PATH: &str = "prefabs/bunker.prefab.ron";
+
+    fn bunker() -> Prefab {
+        let cell = |walkable| PrefabCell {
+            terrain: TerrainType::named("ConcreteWall"),
+            walkable,
+            elevation: 0.0,
+            prop: None,
+            marker: None,
+        };
+        Prefab {
+            name: "bunker".to_string(),
+            legend: BTreeMap::from([('#', cell(false)), ('.', cell(true))]),
+            tiles: vec!["###".to_string(), "#..".to_string(), "###".to_string()],
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_level() {
+        let settings = MapGenSettings::default();
+        let bunker = bunker();
+        let path = AssetPath::from(BUNKER_PATH);
+        let generate = |seed| generate_level(seed, &settings, &[(&path, &bunker)]).tiles;
+        assert_eq!(generate(7), generate(7));
+        assert_ne!(generate(7), generate(8));
+    }
+
+    #[test]
+    fn paints_only_shipped_tile_types() {
+        let source = include_str!("../../assets/tiles/wasteland.tiles.ron");
+        let tile_types: TileTypes = ron::de::from_str(source).unwrap();
+        let path = AssetPath::from(BUNKER_PATH);
+        let level = generate_level(3, &MapGenSettings::default(), &[(&path, &bunker())]);
+        assert_eq!(tile_types.missing_from(&level), vec![]);
+    }
+
+    #[test]
+    fn walkable_area_is_connected_around_the_spawn() {
+        let settings = MapGenSettings::default();
+        let (path, bunker) = (AssetPath::from(BUNKER_PATH), bunker());
+        for seed in 0..20 {
+            let level = generate_level(seed, &settings, &[(&path, &bunker)]);
+            assert_eq!(level.validate(), Ok(()), "seed {seed}");
+            assert!(!level.stamped.is_empty(), "seed {seed}");
+
+            let regions = level.walkable_regions();
+            let (width, depth) = level.size();
+            assert_eq!(regions.len(), 1, "seed {seed}");
+            assert!(regions[0].contains(&level.player_spawn()), "seed {seed}");
+            assert!(regions[0].len() > (width * depth / 2) as usize, "seed {seed}");
+        }
+    }
+}
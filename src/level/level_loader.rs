@@ -0,0 +1,153 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadDirectError};
+use bevy::prelude::*;
+
+use crate::level::level_asset::{Level, LevelLayoutError};
+use crate::level::prefab::{Prefab, StampError};
+use crate::level::tile_types::{TileTypeError, TileTypes};
+
+/// Loads `.level.ron` files into `Level` assets, validating the layout on the way and
+/// stamping in the prefabs they place.
+#[derive(Default, TypePath)]
+pub struct LevelLoader;
+
+/// Loads `.prefab.ron` files into `Prefab` assets.
+#[derive(Default, TypePath)]
+pub struct PrefabLoader;
+
+/// Loads `.tiles.ron` files into `TileTypes` assets.
+#[derive(Default, TypePath)]
+pub struct TileTypesLoader;
+
+#[derive(thiserror::Error, Debug)]
+pub enum LevelLoadError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("syntax error at line {line}, column {column}: {message}",
+        line = .0.position.line, column = .0.position.col, message = .0.code)]
+    Syntax(#[from] ron::error::SpannedError),
+    #[error("invalid level layout: {0}")]
+    Layout(#[from] LevelLayoutError),
+    #[error("could not load prefab {path:?}: {source}")]
+    Prefab { path: String, source: Box<LoadDirectError> },
+    #[error("could not stamp prefab {index}: {source}")]
+    Stamp { index: usize, source: StampError },
+    #[error("invalid tile types: {0}")]
+    TileTypes(#[from] TileTypeError),
+}
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = LevelLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Level, LevelLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut level: Level = ron::de::from_bytes(&bytes)?;
+        level.validate()?;
+
+        for (index, placement) in level.prefabs.clone().into_iter().enumerate() {
+            let prefab = load_context
+                .loader()
+                .immediate()
+                .load::<Prefab>(placement.prefab.clone())
+                .await
+                .map_err(|source| LevelLoadError::Prefab {
+                    path: placement.prefab.clone(),
+                    source: Box::new(source),
+                })?;
+            level
+                .stamp(prefab.get(), &placement)
+                .map_err(|source| LevelLoadError::Stamp { index, source })?;
+        }
+        // A prefab may have walled in the player spawn
+        level.validate()?;
+        Ok(level)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+impl AssetLoader for PrefabLoader {
+    type Asset = Prefab;
+    type Settings = ();
+    type Error = LevelLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Prefab, LevelLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let prefab: Prefab = ron::de::from_bytes(&bytes)?;
+        prefab.validate()?;
+        Ok(prefab)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab.ron"]
+    }
+}
+
+impl AssetLoader for TileTypesLoader {
+    type Asset = TileTypes;
+    type Settings = ();
+    type Error = LevelLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<TileTypes, LevelLoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let tile_types: TileTypes = ron::de::from_bytes(&bytes)?;
+        tile_types.validate()?;
+        Ok(tile_types)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tiles.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_level_is_valid() {
+        let source = include_str!("../../assets/levels/wasteland.level.ron");
+        let mut level: Level = ron::de::from_str(source).unwrap();
+        assert_eq!(level.validate(), Ok(()));
+        assert_eq!(level.size(), (45, 45));
+
+        for placement in level.prefabs.clone() {
+            let path = format!("assets/{}", placement.prefab);
+            let source = std::fs::read_to_string(&path).unwrap();
+            let prefab: Prefab = ron::de::from_str(&source).unwrap();
+            assert_eq!(prefab.validate(), Ok(()), "{path}");
+            assert_eq!(level.stamp(&prefab, &placement), Ok(()), "{path}");
+        }
+        assert_eq!(level.validate(), Ok(()));
+        assert!(level.spawn_markers.len() >= 3);
+    }
+
+    #[test]
+    fn syntax_errors_point_at_line_and_column() {
+        let source = "(\n    chunk_cols: 1,\n    chunk_rows: one,\n)";
+        let error = LevelLoadError::from(ron::de::from_str::<Level>(source).unwrap_err());
+        assert!(error.to_string().starts_with("syntax error at line 3, column 17"), "{error}");
+    }
+}
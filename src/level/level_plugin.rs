@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::{ConfigureLoadingState, LoadingStateAppExt, LoadingStateConfig};
+
+use crate::level::level_asset::{Level, LevelAssets};
+use crate::level::level_generator::{MapGenSettings, generate_procedural_level};
+use crate::level::level_loader::{LevelLoader, PrefabLoader, TileTypesLoader};
+use crate::level::prefab::{Prefab, PrefabAssets};
+use crate::level::tile_types::{
+    TileTypeAssets, TileTypeRegistry, TileTypes, build_tile_type_registry,
+};
+use crate::systems::animation::PlayerLoadingState;
+use crate::systems::level_plane_system::spawn_level_chunk_grid;
+
+/// Loads the level file and the tile types alongside the player assets; the plane is
+/// spawned from them once loading is done. With `--procedural` a generated level takes its
+/// place, using the prefabs in `PrefabAssets` as landmarks.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Level>()
+            .init_asset::<Prefab>()
+            .init_asset::<TileTypes>()
+            .init_asset_loader::<LevelLoader>()
+            .init_asset_loader::<PrefabLoader>()
+            .init_asset_loader::<TileTypesLoader>()
+            .init_resource::<TileTypeRegistry>()
+            .configure_loading_state(
+                LoadingStateConfig::new(PlayerLoadingState::Loading)
+                    .load_collection::<LevelAssets>()
+                    .load_collection::<PrefabAssets>()
+                    .load_collection::<TileTypeAssets>(),
+            )
+            .add_systems(
+                OnEnter(PlayerLoadingState::Ready),
+                build_tile_type_registry.before(spawn_level_chunk_grid),
+            );
+
+        if std::env::args().any(|arg| arg == "--procedural") {
+            app.init_resource::<MapGenSettings>().add_systems(
+                OnEnter(PlayerLoadingState::Ready),
+                generate_procedural_level.before(build_tile_type_registry),
+            );
+        }
+    }
+}
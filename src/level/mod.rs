@@ -0,0 +1,9 @@
+pub mod level_asset;
+pub mod level_generator;
+mod level_loader;
+mod level_plugin;
+pub mod prefab;
+pub mod tile_types;
+
+pub use level_asset::{Level, LevelAssets};
+pub use level_plugin::LevelPlugin;
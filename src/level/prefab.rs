@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::TerrainType;
+use crate::level::level_asset::{Level, LevelLayoutError, LevelTile};
+
+/// Symbol for prefab cells that leave the level's tile as it is
+pub const TRANSPARENT: char = ' ';
+
+/// A hand-authored structure (gas station, bunker, ...) described by a `.prefab.ron` file
+/// and stamped into levels.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Prefab {
+    pub name: String,
+    /// Symbols used in `tiles`; a space is never stamped
+    pub legend: BTreeMap<char, PrefabCell>,
+    /// One string per row of tiles, first row at z = 0 before rotation
+    pub tiles: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrefabCell {
+    #[serde(default)]
+    pub terrain: TerrainType,
+    #[serde(default = "walkable_by_default")]
+    pub walkable: bool,
+    #[serde(default)]
+    pub elevation: f32,
+    /// Decoration placed on the tile
+    #[serde(default)]
+    pub prop: Option<String>,
+    #[serde(default)]
+    pub marker: Option<SpawnMarker>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum SpawnMarker {
+    Enemy,
+    Loot,
+}
+
+/// Quarter turns about +y, counter-clockwise seen from above: `R90` turns the prefab's +x
+/// towards -z.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum PrefabRotation {
+    #[default]
+    R0,
+    R90,
+    R180,
+    R270,
+}
+
+/// What a prefab may cover. Other prefabs are never overwritten.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum OverlapRule {
+    /// Overwrite whatever terrain is there
+    #[default]
+    Replace,
+    /// Only fit where every covered tile is walkable
+    WalkableOnly,
+}
+
+/// Where and how a level places a prefab.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PrefabPlacement {
+    pub prefab: String, // Asset path of the `.prefab.ron` file
+    pub at: (i32, i32), // Level tile of the rotated footprint's lowest corner
+    #[serde(default)]
+    pub rotation: PrefabRotation,
+    #[serde(default)]
+    pub mirrored: bool, // Flipped along x before rotating
+    #[serde(default)]
+    pub overlap: OverlapRule,
+}
+
+/// Prop on a level tile, placed in the editor or left by a stamped prefab.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PlacedProp {
+    pub coord: (i32, i32),
+    pub name: String,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum StampError {
+    #[error("prefab {prefab:?} does not fit in the level at {coord:?}")]
+    OutsideLevel { prefab: String, coord: (i32, i32) },
+    #[error("prefab {prefab:?} overlaps another prefab at {coord:?}")]
+    Overlap { prefab: String, coord: (i32, i32) },
+    #[error("prefab {prefab:?} would cover blocked tile {coord:?}")]
+    Blocked { prefab: String, coord: (i32, i32) },
+}
+
+/// Prefabs available to the map generator.
+#[derive(AssetCollection, Resource)]
+pub struct PrefabAssets {
+    #[asset(
+        paths(
+            "prefabs/gas_station.prefab.ron",
+            "prefabs/bunker.prefab.ron",
+            "prefabs/wrecked_convoy.prefab.ron"
+        ),
+        collection(typed)
+    )]
+    pub prefabs: Vec<Handle<Prefab>>,
+}
+
+fn walkable_by_default() -> bool {
+    true
+}
+
+impl Prefab {
+    /// Width and depth in tiles, before rotation.
+    pub fn size(&self) -> (i32, i32) {
+        let width = self.tiles.first().map_or(0, |row| row.chars().count());
+        (width as i32, self.tiles.len() as i32)
+    }
+
+    /// Every stamped cell with its prefab-local coordinate.
+    pub fn cells(&self) -> impl Iterator<Item = ((i32, i32), &PrefabCell)> {
+        self.tiles.iter().enumerate().flat_map(move |(z, row)| {
+            row.chars().enumerate().filter(|&(_, symbol)| symbol != TRANSPARENT).filter_map(
+                move |(x, symbol)| {
+                    self.legend.get(&symbol).map(|cell| ((x as i32, z as i32), cell))
+                },
+            )
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), LevelLayoutError> {
+        let (width, _) = self.size();
+        if width == 0 {
+            return Err(LevelLayoutError::Empty);
+        }
+        for (row, symbols) in self.tiles.iter().enumerate() {
+            let found = symbols.chars().count();
+            if found != width as usize {
+                return Err(LevelLayoutError::RowWidth { row, expected: width as usize, found });
+            }
+            if let Some((column, symbol)) = symbols
+                .chars()
+                .enumerate()
+                .find(|&(_, symbol)| symbol != TRANSPARENT && !self.legend.contains_key(&symbol))
+            {
+                return Err(LevelLayoutError::UnknownSymbol { row, column, symbol });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PrefabPlacement {
+    /// Footprint size in level tiles once rotated.
+    pub fn footprint(&self, prefab: &Prefab) -> (i32, i32) {
+        let (width, depth) = prefab.size();
+        match self.rotation {
+            PrefabRotation::R0 | PrefabRotation::R180 => (width, depth),
+            PrefabRotation::R90 | PrefabRotation::R270 => (depth, width),
+        }
+    }
+
+    /// Level tile a prefab-local coordinate lands on.
+    pub fn level_coord(&self, prefab: &Prefab, (x, z): (i32, i32)) -> (i32, i32) {
+        let (width, depth) = prefab.size();
+        let x = if self.mirrored { width - 1 - x } else { x };
+        let (x, z) = match self.rotation {
+            PrefabRotation::R0 => (x, z),
+            PrefabRotation::R90 => (z, width - 1 - x),
+            PrefabRotation::R180 => (width - 1 - x, depth - 1 - z),
+            PrefabRotation::R270 => (depth - 1 - z, x),
+        };
+        (self.at.0 + x, self.at.1 + z)
+    }
+}
+
+impl Level {
+    /// Writes `prefab` into the tile map. Either the whole prefab is stamped or, on error,
+    /// nothing is.
+    pub fn stamp(
+        &mut self,
+        prefab: &Prefab,
+        placement: &PrefabPlacement,
+    ) -> Result<(), StampError> {
+        let name = || prefab.name.clone();
+        let mut cells = Vec::new();
+        for (local, cell) in prefab.cells() {
+            let coord = placement.level_coord(prefab, local);
+            if !self.contains(coord) {
+                return Err(StampError::OutsideLevel { prefab: name(), coord });
+            }
+            if self.stamped.contains(&coord) {
+                return Err(StampError::Overlap { prefab: name(), coord });
+            }
+            if placement.overlap == OverlapRule::WalkableOnly && !self.tile(coord).walkable {
+                return Err(StampError::Blocked { prefab: name(), coord });
+            }
+            cells.push((coord, cell));
+        }
+
+        for (coord, cell) in cells {
+            let symbol = self.symbol_for(LevelTile {
+                terrain: cell.terrain,
+                walkable: cell.walkable,
+                elevation: cell.elevation,
+            });
+            self.set_symbol(coord, symbol);
+            if let Some(name) = &cell.prop {
+                self.props.push(PlacedProp { coord, name: name.clone() });
+            }
+            if let Some(marker) = cell.marker {
+                self.spawn_markers.push((coord, marker));
+            }
+            self.stamped.insert(coord);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prefab() -> Prefab {
+        Prefab {
+            name: "shed".to_string(),
+            legend: BTreeMap::from([
+                (
+                    '#',
+                    PrefabCell {
+                        terrain: TerrainType::named("ConcreteWall"),
+                        walkable: false,
+                        elevation: 1.0,
+                        prop: None,
+                        marker: None,
+                    },
+                ),
+                (
+                    'E',
+                    PrefabCell {
+                        terrain: TerrainType::GROUND,
+                        walkable: true,
+                        elevation: 0.0,
+                        prop: Some("crate".to_string()),
+                        marker: Some(SpawnMarker::Enemy),
+                    },
+                ),
+            ]),
+            tiles: vec!["##".to_string(), "E ".to_string(), "##".to_string()],
+        }
+    }
+
+    fn level() -> Level {
+        Level { chunk_cols: 1, chunk_rows: 1, tiles_per_chunk: 6, tile_size: 2.0, ..default() }
+    }
+
+    #[test]
+    fn rotates_and_mirrors_footprint() {
+        let prefab = prefab();
+        let placement = |rotation, mirrored| PrefabPlacement {
+            prefab: String::new(),
+            at: (1, 1),
+            rotation,
+            mirrored,
+            overlap: OverlapRule::Replace,
+        };
+        let marker = (0, 1);
+        assert_eq!(placement(PrefabRotation::R0, false).level_coord(&prefab, marker), (1, 2));
+        assert_eq!(placement(PrefabRotation::R0, true).level_coord(&prefab, marker), (2, 2));
+        assert_eq!(placement(PrefabRotation::R90, false).level_coord(&prefab, marker), (2, 2));
+        assert_eq!(placement(PrefabRotation::R180, false).level_coord(&prefab, marker), (2, 2));
+        assert_eq!(placement(PrefabRotation::R90, false).footprint(&prefab), (3, 2));
+
+        // A step along the prefab's first row points along -z after R90 and +z after R270
+        let step = |rotation| {
+            let placement = placement(rotation, false);
+            let (from, to) =
+                (placement.level_coord(&prefab, (0, 0)), placement.level_coord(&prefab, (1, 0)));
+            (to.0 - from.0, to.1 - from.1)
+        };
+        assert_eq!(step(PrefabRotation::R90), (0, -1));
+        assert_eq!(step(PrefabRotation::R180), (-1, 0));
+        assert_eq!(step(PrefabRotation::R270), (0, 1));
+    }
+
+    #[test]
+    fn stamps_tiles_props_and_markers() {
+        let (prefab, mut level) = (prefab(), level());
+        let placement = PrefabPlacement {
+            prefab: String::new(),
+            at: (2, 2),
+            rotation: PrefabRotation::R0,
+            mirrored: false,
+            overlap: OverlapRule::Replace,
+        };
+        assert_eq!(level.stamp(&prefab, &placement), Ok(()));
+        assert!(!level.tile((2, 2)).walkable);
+        assert!(level.tile((3, 3)).walkable); // Transparent cell
+        assert_eq!(level.spawn_markers, vec![((2, 3), SpawnMarker::Enemy)]);
+        assert_eq!(level.props.len(), 1);
+        assert_eq!(level.validate(), Ok(()));
+
+        // A second copy may not overlap the first, and a failed stamp changes nothing
+        let overlapping = PrefabPlacement { at: (3, 2), ..placement.clone() };
+        assert!(matches!(level.stamp(&prefab, &overlapping), Err(StampError::Overlap { .. })));
+        assert!(level.tile((4, 2)).walkable);
+        let outside = PrefabPlacement { at: (5, 5), ..placement };
+        assert!(matches!(level.stamp(&prefab, &outside), Err(StampError::OutsideLevel { .. })));
+    }
+}
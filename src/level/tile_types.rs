@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+
+use crate::components::TerrainType;
+use crate::level::level_asset::{Level, LevelAssets};
+use crate::materials::tile_material::TileMaterial;
+
+/// Every tile type the game knows, as described by a `.tiles.ron` file.
+///
+/// Levels and prefabs refer to types by name, so a new kind of ground only needs an entry
+/// here. `Ground` must be present: tiles of unknown types are drawn and walked as ground.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct TileTypes {
+    pub types: BTreeMap<TerrainType, TileTypeDef>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TileTypeDef {
+    #[serde(default = "walkable_by_default")]
+    pub walkable: bool,
+    /// Multiplier applied to distance when pathing and to time when walking
+    pub movement_cost: f32,
+    #[serde(default)]
+    pub material: TileMaterial,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum TileTypeError {
+    #[error("tile type {0:?} is missing; unknown tile types fall back to it")]
+    MissingDefault(TerrainType),
+    #[error("tile type {terrain:?} costs {cost}, below the minimum of {min}",
+        min = TerrainType::MIN_MOVEMENT_COST)]
+    CostTooLow { terrain: TerrainType, cost: f32 },
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct TileTypeAssets {
+    #[asset(path = "tiles/wasteland.tiles.ron")]
+    pub tile_types: Handle<TileTypes>,
+}
+
+/// A tile type with its material created.
+#[derive(Clone, Debug, Default)]
+pub struct TileType {
+    pub walkable: bool,
+    pub movement_cost: f32,
+    pub material: Handle<StandardMaterial>,
+}
+
+/// Tile types of the running game, looked up when chunks spawn.
+#[derive(Resource, Debug)]
+pub struct TileTypeRegistry {
+    types: HashMap<TerrainType, TileType>,
+    fallback: TileType,
+}
+
+fn walkable_by_default() -> bool {
+    true
+}
+
+impl TileTypes {
+    pub fn validate(&self) -> Result<(), TileTypeError> {
+        if !self.types.contains_key(&TerrainType::GROUND) {
+            return Err(TileTypeError::MissingDefault(TerrainType::GROUND));
+        }
+        // Heuristics assume no step is cheaper than the cheapest terrain
+        match self.types.iter().find(|(_, def)| def.movement_cost < TerrainType::MIN_MOVEMENT_COST)
+        {
+            Some((&terrain, def)) => {
+                Err(TileTypeError::CostTooLow { terrain, cost: def.movement_cost })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Tile types used by `level`'s legend that are not defined here.
+    pub fn missing_from(&self, level: &Level) -> Vec<TerrainType> {
+        let mut missing: Vec<TerrainType> = level
+            .legend
+            .values()
+            .map(|tile| tile.terrain)
+            .filter(|terrain| !self.types.contains_key(terrain))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+impl Default for TileTypeRegistry {
+    fn default() -> Self {
+        let fallback = TileType { walkable: true, movement_cost: 1.0, material: default() };
+        Self { types: HashMap::new(), fallback }
+    }
+}
+
+impl TileTypeRegistry {
+    pub fn new(
+        tile_types: &TileTypes,
+        mut material_for: impl FnMut(&TileMaterial) -> Handle<StandardMaterial>,
+    ) -> Self {
+        let types: HashMap<TerrainType, TileType> = tile_types
+            .types
+            .iter()
+            .map(|(&terrain, def)| {
+                let tile_type = TileType {
+                    walkable: def.walkable,
+                    movement_cost: def.movement_cost,
+                    material: material_for(&def.material),
+                };
+                (terrain, tile_type)
+            })
+            .collect();
+        let fallback = types.get(&TerrainType::GROUND).cloned().unwrap_or_default();
+        Self { types, fallback }
+    }
+
+    /// Names of every registered tile type, in order.
+    pub fn terrains(&self) -> Vec<TerrainType> {
+        let mut terrains: Vec<TerrainType> = self.types.keys().copied().collect();
+        terrains.sort();
+        terrains
+    }
+
+    /// The tile type called `terrain`, or ground when there is none.
+    pub fn get(&self, terrain: TerrainType) -> &TileType {
+        self.types.get(&terrain).unwrap_or(&self.fallback)
+    }
+}
+
+/// Creates the materials of every tile type before the first chunk spawns.
+pub fn build_tile_type_registry(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    tile_type_assets: Res<TileTypeAssets>,
+    tile_types: Res<Assets<TileTypes>>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+) {
+    let Some(tile_types) = tile_types.get(&tile_type_assets.tile_types) else {
+        error!("Tile types are missing, every tile is drawn as plain ground");
+        return;
+    };
+    if let Some(level) = level_assets.current(&levels) {
+        for terrain in tile_types.missing_from(level) {
+            warn!("Level uses unknown tile type {:?}, treating it as ground", terrain);
+        }
+    }
+    let registry = TileTypeRegistry::new(tile_types, |material| {
+        materials.add(material.to_standard_material(&asset_server))
+    });
+    info!("Registered {} tile types", registry.types.len());
+    commands.insert_resource(registry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::prefab::Prefab;
+
+    fn shipped_tile_types() -> TileTypes {
+        ron::de::from_str(include_str!("../../assets/tiles/wasteland.tiles.ron")).unwrap()
+    }
+
+    #[test]
+    fn shipped_levels_only_use_known_tile_types() {
+        let tile_types = shipped_tile_types();
+        assert_eq!(tile_types.validate(), Ok(()));
+
+        let level: Level =
+            ron::de::from_str(include_str!("../../assets/levels/wasteland.level.ron")).unwrap();
+        assert_eq!(tile_types.missing_from(&level), vec![]);
+        for placement in &level.prefabs {
+            let source = std::fs::read_to_string(format!("assets/{}", placement.prefab)).unwrap();
+            let prefab: Prefab = ron::de::from_str(&source).unwrap();
+            for (_, cell) in prefab.cells() {
+                assert!(tile_types.types.contains_key(&cell.terrain), "{}", cell.terrain);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_types_fall_back_to_ground() {
+        let mut tile_types = shipped_tile_types();
+        let registry = TileTypeRegistry::new(&tile_types, |_| Handle::default());
+        let ground = registry.get(TerrainType::GROUND).movement_cost;
+        assert_eq!(registry.get(TerrainType::named("Lava")).movement_cost, ground);
+        assert!(!registry.get(TerrainType::named("ConcreteWall")).walkable);
+
+        let asphalt = TerrainType::named("Asphalt");
+        tile_types.types.get_mut(&asphalt).unwrap().movement_cost = 0.5;
+        assert_eq!(
+            tile_types.validate(),
+            Err(TileTypeError::CostTooLow { terrain: asphalt, cost: 0.5 })
+        );
+    }
+}
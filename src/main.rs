@@ -5,22 +5,30 @@ mod systems;
 mod enemy;
 mod shared;
 mod player;
+mod level;
+mod editor;
 
 use bevy::app::App;
 use bevy::prelude::*;
+use crate::editor::EditorPlugin;
 use crate::enemy::EnemyPlugin;
-use crate::materials::pavement::CheckedFloorMaterials;
-use crate::plugins::{CameraPlugin, PlayerPlugin, TestPlanePlugin, TileSelectionPlugin};
+use crate::level::LevelPlugin;
+use crate::plugins::{
+    CameraPlugin, PlayerPlugin, RngPlugin, SimulationPlugin, TestPlanePlugin, TileSelectionPlugin,
+};
 
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .init_resource::<CheckedFloorMaterials>()
         .add_plugins(MeshPickingPlugin)
+        .add_plugins(RngPlugin)
+        .add_plugins(SimulationPlugin)
         .add_plugins(PlayerPlugin)
+        .add_plugins(LevelPlugin)
         .add_plugins(EnemyPlugin)
         .add_plugins(TestPlanePlugin)
         .add_plugins(CameraPlugin)
         .add_plugins(TileSelectionPlugin)
+        .add_plugins(EditorPlugin)
         .run();
 }
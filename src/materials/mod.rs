@@ -1 +1 @@
-pub mod pavement;
+pub mod tile_material;
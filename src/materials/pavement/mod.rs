@@ -1,3 +0,0 @@
-pub mod checked_floor;
-
-pub use checked_floor::*;
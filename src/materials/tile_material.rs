@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Look of a tile type, as written in a `.tiles.ron` file.
+///
+/// Textures are paths under `assets/` and tint `color`, which is sRGB. `arm_texture` packs
+/// ambient occlusion, roughness and metallic into one image, like the Poly Haven sets.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, default)]
+pub struct TileMaterial {
+    pub color: (f32, f32, f32),
+    pub roughness: f32, // Modulated by the ARM texture when there is one
+    pub metallic: f32,
+    pub texture: Option<String>,
+    pub normal_map: Option<String>, // OpenGL convention
+    pub arm_texture: Option<String>,
+}
+
+impl Default for TileMaterial {
+    fn default() -> Self {
+        Self {
+            color: (1.0, 1.0, 1.0),
+            roughness: 0.8,
+            metallic: 0.0,
+            texture: None,
+            normal_map: None,
+            arm_texture: None,
+        }
+    }
+}
+
+impl TileMaterial {
+    pub fn to_standard_material(&self, asset_server: &AssetServer) -> StandardMaterial {
+        let load =
+            |path: &Option<String>| path.as_ref().map(|path| asset_server.load(path.clone()));
+        let arm_texture = load(&self.arm_texture);
+        let (red, green, blue) = self.color;
+        StandardMaterial {
+            base_color: Color::srgb(red, green, blue),
+            base_color_texture: load(&self.texture),
+            normal_map_texture: load(&self.normal_map),
+            occlusion_texture: arm_texture.clone(),
+            metallic_roughness_texture: arm_texture,
+            perceptual_roughness: self.roughness,
+            metallic: self.metallic,
+            ..default()
+        }
+    }
+}
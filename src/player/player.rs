@@ -3,6 +3,27 @@ use bevy::prelude::*;
 #[derive(Component)]
 pub struct Player;
 
+/// Spent while running, regained while walking or standing still.
+#[derive(Component)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    pub drain_rate: f32, // Per second while running
+    pub regen_rate: f32, // Per second otherwise
+}
+
+/// Whether the player asked to run to the current destination.
+#[derive(Component, Default)]
+pub struct RunMode {
+    pub requested: bool,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self { current: 100.0, max: 100.0, drain_rate: 25.0, regen_rate: 15.0 }
+    }
+}
+
 #[derive(Message)]
 pub struct PlayerStartupTileSelectedEvent {
     pub tile_entity: Entity,
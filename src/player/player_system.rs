@@ -1,12 +1,20 @@
 use crate::components::movements::movement::MovementSpeed;
-use crate::player::player::{Player, PlayerStartupTileSelectedEvent};
-use crate::components::{PlaneChunk, TilePosition, TileRegistry};
+use crate::components::movements::waypoints::Waypoints;
+use crate::player::player::{Player, PlayerStartupTileSelectedEvent, RunMode, Stamina};
+use crate::components::{
+    MovementState, PlaneChunk, TilePosition, TileRegistry, TransformInterpolation,
+};
+use crate::level::{Level, LevelAssets};
 use crate::shared::CharacterType;
 use bevy::prelude::*;
 
 pub fn init_player(mut commands: Commands) {
     commands.spawn(Player {}).insert((
         MovementSpeed::default(),
+        Stamina::default(),
+        RunMode::default(),
+        Waypoints::default(),
+        TransformInterpolation::default(),
         CharacterType::Player,
         Name::new("Player"),
     ));
@@ -18,26 +26,63 @@ pub fn init_player_startup_tile(
     mut player_query: Query<Entity, (With<Player>, Without<Transform>)>,
     mut player_startup_tile_selected_events: MessageWriter<PlayerStartupTileSelectedEvent>,
     tile_registry: Res<TileRegistry>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
     grid_query: Query<(&Transform, &PlaneChunk), Without<Player>>,
 ) {
     if let Some(player_entity) = player_query.single_mut().ok() {
-        if let Some(middle) =
-            crate::systems::plane_chunk_system::get_middle(&tile_registry.tiles_by_coord)
-        {
-            commands.entity(player_entity).insert(TilePosition { tile: Some(middle.1.clone()) });
+        let Some(spawn) = level_assets.current(&levels).map(Level::player_spawn) else {
+            warn!("No level loaded, cannot place the player");
+            return;
+        };
+        if let Some(spawn_tile) = tile_registry.grid.get(spawn) {
+            commands.entity(player_entity).insert(TilePosition { tile: Some(spawn_tile) });
             if let Some(world_pos) =
                 crate::systems::plane_chunk_system::calculate_tile_world_position(
-                    *middle.0,
+                    spawn,
                     &grid_query,
                 )
             {
                 commands.entity(player_entity).insert(Transform::from_translation(world_pos));
-                info!("Player positioned at middle tile: {:?}", world_pos);
+                info!("Player positioned at spawn tile: {:?}", world_pos);
             }
 
             player_startup_tile_selected_events
-                .write(PlayerStartupTileSelectedEvent { tile_entity: middle.1.clone() });
+                .write(PlayerStartupTileSelectedEvent { tile_entity: spawn_tile });
             info!("Player startup tile selected");
         }
     }
 }
+
+/// Share of the maximum stamina needed before the player can start running again
+const MIN_STAMINA_TO_RUN: f32 = 0.2;
+
+/// Switches the player between walking and running, spending stamina while running
+/// and regaining it otherwise.
+pub fn update_stamina(
+    mut player_query: Query<(&mut Stamina, &mut RunMode, &mut MovementState), With<Player>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    for (mut stamina, mut run_mode, mut movement_state) in player_query.iter_mut() {
+        if *movement_state == MovementState::Running {
+            stamina.current = (stamina.current - stamina.drain_rate * delta).max(0.0);
+            if stamina.current <= 0.0 {
+                info!("Out of stamina, falling back to walking");
+                run_mode.requested = false;
+            }
+            if !run_mode.requested {
+                *movement_state = MovementState::Walking;
+            }
+            continue;
+        }
+
+        stamina.current = (stamina.current + stamina.regen_rate * delta).min(stamina.max);
+        if *movement_state == MovementState::Walking
+            && run_mode.requested
+            && stamina.current >= stamina.max * MIN_STAMINA_TO_RUN
+        {
+            *movement_state = MovementState::Running;
+        }
+    }
+}
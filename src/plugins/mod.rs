@@ -1,9 +1,13 @@
 pub mod camera_plugin;
 pub mod plane_plugin;
 pub mod player_plugin;
+pub mod rng_plugin;
+pub mod simulation_plugin;
 pub mod tile_selection_plugin;
 
 pub use camera_plugin::*;
 pub use plane_plugin::*;
 pub use player_plugin::*;
+pub use rng_plugin::*;
+pub use simulation_plugin::*;
 pub use tile_selection_plugin::*;
@@ -1,30 +1,74 @@
 use bevy::prelude::*;
 
 use crate::{
-    components::TileRegistry,
+    components::{
+        TileRegistry,
+        chunk_streaming::{ChunkStreamingConfig, ChunksStreamedEvent},
+    },
     systems::{
-        level_plane_system::spawn_default_chunk_grid,
-        plane_chunk_system::{build_tile_registry, handle_optimized_grid_clicks},
+        chunk_streaming_system::{link_streamed_chunks, stream_chunks},
+        level_plane_system::{spawn_level_chunk_grid, spawn_level_props},
+        plane_chunk_system::{
+            build_tile_registry, handle_optimized_grid_clicks, refresh_chunk_surfaces,
+        },
     },
 };
-use crate::components::movements::movement::MoveRequestEvent;
+use crate::components::movements::hierarchical::ClusterGraph;
+use crate::editor::EditorMode;
+use crate::level::LevelAssets;
+use crate::components::movements::movement::{MoveRequestEvent, PathNotFoundEvent};
+use crate::components::movements::tile_graph_snapshot::PathfindingSnapshot;
+use crate::plugins::PlayerSystemSet;
+use crate::systems::movement::hierarchical_movement::{
+    build_cluster_graph, update_cluster_graph, update_streamed_cluster_graph,
+};
+use crate::systems::movement::path_invalidation::drop_unloaded_paths;
+use crate::systems::movement::path_request_system::update_pathfinding_snapshot;
 use crate::player::player::PlayerStartupTileSelectedEvent;
+use crate::systems::animation::PlayerLoadingState;
 
 pub struct TestPlanePlugin;
 
 impl Plugin for TestPlanePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<TileRegistry>();
+        app.init_resource::<ClusterGraph>();
+        app.init_resource::<PathfindingSnapshot>();
+        app.init_resource::<ChunkStreamingConfig>();
+        app.add_message::<ChunksStreamedEvent>();
         app.add_message::<PlayerStartupTileSelectedEvent>();
         app.add_message::<MoveRequestEvent>();
+        app.add_message::<PathNotFoundEvent>();
+        app.add_systems(
+            OnEnter(PlayerLoadingState::Ready),
+            (spawn_level_chunk_grid, build_tile_registry, build_cluster_graph).chain(),
+        );
+        // app.add_systems(Update, draw_tiles_borders);
         app.add_systems(
-            Startup,
+            Update,
             (
-                spawn_default_chunk_grid,
-                build_tile_registry.after(spawn_default_chunk_grid),
+                handle_optimized_grid_clicks.run_if(in_state(EditorMode::Play)),
+                (spawn_level_props, refresh_chunk_surfaces)
+                    .run_if(resource_exists::<LevelAssets>),
+            ),
+        );
+        app.add_systems(
+            FixedUpdate,
+            (
+                (
+                    stream_chunks,
+                    link_streamed_chunks,
+                    update_streamed_cluster_graph,
+                    drop_unloaded_paths,
+                )
+                    .chain()
+                    .run_if(in_state(PlayerLoadingState::Ready))
+                    .before(PlayerSystemSet::Input),
+                (update_cluster_graph, update_pathfinding_snapshot)
+                    .chain()
+                    .after(link_streamed_chunks)
+                    .before(PlayerSystemSet::Movement),
             ),
         );
-        // app.add_systems(Update, draw_tiles_borders);
-        app.add_systems(Update, handle_optimized_grid_clicks);
     }
 }
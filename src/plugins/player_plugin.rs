@@ -1,14 +1,28 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::{ConfigureLoadingState, LoadingState, LoadingStateAppExt};
 use PlayerLoadingState::Loading;
-use crate::systems::animation::{check_animations_loaded, init_animation_system, movement_state_to_animation, on_play_animation, play_animation_system, start_initial_animation, PendingAnimations, PlayerAssets, PlayerLoadingState};
+use crate::systems::animation::{check_animations_loaded, init_animation_system, movement_state_to_animation, on_play_animation, sync_animation_speed, play_animation_system, start_initial_animation, PendingAnimations, PlayerAssets, PlayerLoadingState};
 use crate::systems::movement::movement_system::{
-    init_player_movement, movement_request_handler, tile_selected_event_handle,
-    update_player_movement,
+    cycle_player_movement_type, init_player_movement, movement_request_handler,
+    tile_selected_event_handle, update_player_movement,
+};
+use crate::systems::movement::hierarchical_movement::build_cluster_graph;
+use crate::systems::movement::path_invalidation::invalidate_blocked_paths;
+use crate::systems::movement::reservation_system::refresh_tile_reservations;
+use crate::components::movements::reservation::TileReservations;
+use crate::components::movements::steering::SteeringConfig;
+use crate::systems::movement::steering_system::update_steering;
+use crate::systems::movement::terrain_system::follow_terrain;
+use crate::systems::movement::path_request_system::{apply_path_results, dispatch_path_requests};
+use crate::components::movements::path_request::{PathRequestQueue, PathfindingConfig};
+use crate::components::movements::waypoints::WaypointEvent;
+use crate::systems::movement::waypoint_system::{
+    advance_patrols, advance_waypoints, draw_waypoints, edit_waypoints_from_keys,
+    handle_waypoint_events,
 };
 
 use crate::components::PlayAnimation;
-use crate::player::player_system::{init_player, init_player_startup_tile};
+use crate::player::player_system::{init_player, init_player_startup_tile, update_stamina};
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum PlayerSystemSet {
@@ -29,11 +43,19 @@ impl Plugin for PlayerPlugin {
             )
             .add_systems(OnEnter(PlayerLoadingState::Ready), init_animation_system.after(init_player_movement))
             .add_systems(OnEnter(PlayerLoadingState::Ready), play_animation_system.after(init_animation_system))
-            .add_systems(OnEnter(PlayerLoadingState::Ready), init_player_startup_tile.after(init_animation_system))
+            .add_systems(
+                OnEnter(PlayerLoadingState::Ready),
+                init_player_startup_tile.after(init_animation_system).after(build_cluster_graph),
+            )
         ;
 
         app
             .add_message::<PlayAnimation>()
+            .add_message::<WaypointEvent>()
+            .init_resource::<PathRequestQueue>()
+            .init_resource::<PathfindingConfig>()
+            .init_resource::<TileReservations>()
+            .init_resource::<SteeringConfig>()
             .add_systems(Startup, init_player)
             .add_systems(Startup, init_player_movement.after(init_player))
             .configure_sets(
@@ -50,13 +72,39 @@ impl Plugin for PlayerPlugin {
                 (
                     check_animations_loaded
                         .run_if(any_with_component::<PendingAnimations>),
-                    tile_selected_event_handle.in_set(PlayerSystemSet::Input),
-                    movement_request_handler.in_set(PlayerSystemSet::Movement),
-                    update_player_movement.in_set(PlayerSystemSet::Update),
+                    edit_waypoints_from_keys.in_set(PlayerSystemSet::Input),
+                    cycle_player_movement_type.in_set(PlayerSystemSet::Input),
+                    draw_waypoints,
                     movement_state_to_animation.in_set(PlayerSystemSet::Update),
+                    sync_animation_speed.in_set(PlayerSystemSet::Update),
                     start_initial_animation.in_set(PlayerSystemSet::Update),
                     on_play_animation.in_set(PlayerSystemSet::Update),
                 ),
+            )
+            // Simulation; see `SimulationPlugin`
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Chained throughout: messages and reservations have to come out in the
+                    // same order on every run
+                    (
+                        tile_selected_event_handle,
+                        handle_waypoint_events,
+                        advance_waypoints,
+                        advance_patrols,
+                        invalidate_blocked_paths,
+                        refresh_tile_reservations,
+                    )
+                        .chain()
+                        .in_set(PlayerSystemSet::Input),
+                    // Results come first so a search gets every tick up to its deadline to run
+                    (apply_path_results, movement_request_handler, dispatch_path_requests)
+                        .chain()
+                        .in_set(PlayerSystemSet::Movement),
+                    (update_stamina, update_player_movement, update_steering, follow_terrain)
+                        .chain()
+                        .in_set(PlayerSystemSet::Update),
+                ),
             );
     }
 }
@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+use crate::shared::GameRng;
+
+/// Seeds the game-wide `GameRng`; pass `--seed <n>` to replay a run.
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let rng = GameRng::from_args();
+        info!("Game seed: {} (replay with --seed {})", rng.seed(), rng.seed());
+        app.insert_resource(rng);
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+use crate::components::{SIMULATION_HZ, SimulationTick};
+use crate::plugins::PlayerSystemSet;
+use crate::systems::simulation_system::{
+    advance_simulation_tick, interpolate_transforms, record_previous_transforms, record_simulated_transforms,
+    restore_simulated_transforms,
+};
+
+/// Runs gameplay at a fixed rate so identical inputs replay identically.
+///
+/// Movement, pathfinding and AI live in `FixedUpdate` under `PlayerSystemSet`; input,
+/// animation, gizmos and the camera stay in `Update`. Entities with a
+/// `TransformInterpolation` are drawn between the last two ticks.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .init_resource::<SimulationTick>()
+            .configure_sets(
+                FixedUpdate,
+                (PlayerSystemSet::Input, PlayerSystemSet::Movement, PlayerSystemSet::Update)
+                    .chain(),
+            )
+            .add_systems(
+                RunFixedMainLoop,
+                (
+                    restore_simulated_transforms
+                        .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop),
+                    interpolate_transforms.in_set(RunFixedMainLoopSystems::AfterFixedMainLoop),
+                ),
+            )
+            .add_systems(FixedFirst, (advance_simulation_tick, record_previous_transforms))
+            .add_systems(FixedLast, record_simulated_transforms);
+    }
+}
@@ -1,4 +1,13 @@
-use crate::{components::TileSelectedEvent, systems::tile_selection_system::handle_tile_selection};
+use crate::{
+    components::{TileHighlight, TileSelectedEvent},
+    systems::{
+        tile_highlight_system::{
+            spawn_tile_overlays, sync_tile_flags, track_hovered_tile, track_selected_tile,
+            update_tile_overlays,
+        },
+        tile_selection_system::{handle_tile_selection, handle_unreachable_tile},
+    },
+};
 use bevy::prelude::*;
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
@@ -8,7 +17,21 @@ pub struct TileSelectionPlugin;
 impl Plugin for TileSelectionPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<TileSelectedEvent>()
+            .init_resource::<TileHighlight>()
             .configure_sets(Update, (InputSet,))
-            .add_systems(Update, handle_tile_selection.in_set(InputSet));
+            .add_systems(Update, handle_tile_selection.in_set(InputSet))
+            .add_systems(Update, handle_unreachable_tile)
+            .add_systems(
+                Update,
+                (
+                    track_hovered_tile,
+                    track_selected_tile,
+                    sync_tile_flags,
+                    spawn_tile_overlays,
+                    update_tile_overlays,
+                )
+                    .chain()
+                    .after(InputSet),
+            );
     }
 }
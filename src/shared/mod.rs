@@ -1,3 +1,5 @@
+pub mod rng;
 pub mod shared_components;
 
+pub use rng::{GameRng, RngStream};
 pub use shared_components::CharacterType;
@@ -1,11 +1,15 @@
-use bevy::prelude::{Changed, Entity, MessageWriter, Query, With};
-use crate::components::{MovementState, PlayAnimation};
+use crate::components::movements::movement::{Movement, MovementSpeed};
+use crate::components::{ModelAnimationGraph, MovementState, PlayAnimation};
 use crate::player::player::Player;
 use crate::systems::animation::{IDLE, RUN, WALK};
+use bevy::prelude::{AnimationPlayer, Changed, Children, Entity, MessageWriter, Query, With};
+
+/// Slowest playback of the walk and run clips, so the legs keep moving when starting off
+const MIN_PLAYBACK_SPEED: f32 = 0.3;
 
 pub fn movement_state_to_animation(
     mut play_animation_writer: MessageWriter<PlayAnimation>,
-    state_query: Query<(Entity, &MovementState), (Changed<MovementState>, With<Player>)>
+    state_query: Query<(Entity, &MovementState), (Changed<MovementState>, With<Player>)>,
 ) {
     for (entity, movement_state) in state_query.iter() {
         let animation_name = match movement_state {
@@ -19,4 +23,36 @@ pub fn movement_state_to_animation(
             model_animation_graph: entity,
         });
     }
-}
\ No newline at end of file
+}
+
+/// Plays the walk and run clips at the player's actual speed, so feet don't slide
+/// while accelerating or braking.
+pub fn sync_animation_speed(
+    players: Query<
+        (Entity, &Movement, &MovementSpeed, &MovementState, &ModelAnimationGraph),
+        With<Player>,
+    >,
+    children: Query<&Children>,
+    mut animation_players: Query<&mut AnimationPlayer>,
+) {
+    for (entity, movement, speed, movement_state, model_graph) in players.iter() {
+        let animation_name = match movement_state {
+            MovementState::Idle => continue,
+            MovementState::Walking => WALK,
+            MovementState::Running => RUN,
+        };
+        let Some(&animation_id) = model_graph.animations.get(animation_name) else {
+            continue;
+        };
+        let playback_speed = (movement.current_speed / speed.top_speed(movement_state))
+            .clamp(MIN_PLAYBACK_SPEED, 1.0);
+
+        for child in children.iter_descendants(entity) {
+            if let Ok(mut player) = animation_players.get_mut(child)
+                && let Some(animation) = player.animation_mut(animation_id)
+            {
+                animation.set_speed(playback_speed);
+            }
+        }
+    }
+}
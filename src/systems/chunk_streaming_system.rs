@@ -0,0 +1,134 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::components::chunk_streaming::{ChunkStreamingConfig, ChunksStreamedEvent};
+use crate::components::{PlaneChunk, Tile, TileIndex, TileRegistry};
+use crate::level::level_generator::world_tile;
+use crate::level::level_asset::LevelTile;
+use crate::level::tile_types::TileTypeRegistry;
+use crate::level::{Level, LevelAssets};
+use crate::player::player::Player;
+use crate::shared::GameRng;
+use crate::systems::plane_chunk_system::spawn_single_chunk_grid;
+
+/// The level being streamed, the player it is streamed around and the seed of the
+/// wilderness beyond its edge.
+#[derive(SystemParam)]
+pub struct StreamingContext<'w, 's> {
+    config: Res<'w, ChunkStreamingConfig>,
+    game_rng: Res<'w, GameRng>,
+    level_assets: Res<'w, LevelAssets>,
+    levels: Res<'w, Assets<Level>>,
+    player_query: Query<'w, 's, &'static Transform, With<Player>>,
+}
+
+impl StreamingContext<'_, '_> {
+    /// The tile at a global coordinate, inside the level or in the wilderness around it.
+    pub fn tile(&self, level: &Level, coord: (i32, i32)) -> LevelTile {
+        world_tile(level, self.game_rng.seed(), coord)
+    }
+
+    pub fn level(&self) -> Option<&Level> {
+        self.level_assets.current(&self.levels)
+    }
+
+    /// The current level and the chunk the player stands in.
+    fn level_and_center(&self) -> Option<(&Level, (i32, i32))> {
+        let level = self.level()?;
+        let player_transform = self.player_query.single().ok()?;
+        Some((level, level.chunk_at(player_transform.translation)))
+    }
+}
+
+/// Spawns chunks coming into range of the player and despawns those left far behind.
+///
+/// Chunks past the edge of the level are filled with wilderness from the game seed, so the
+/// world never ends. Tiles of despawned chunks leave the `TileRegistry` right away; tiles of
+/// spawned ones are added by `link_streamed_chunks` once their entities exist.
+pub fn stream_chunks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_types: Res<TileTypeRegistry>,
+    mut tile_registry: ResMut<TileRegistry>,
+    mut streamed_writer: MessageWriter<ChunksStreamedEvent>,
+    context: StreamingContext,
+    chunk_query: Query<(Entity, &PlaneChunk)>,
+) {
+    let Some((level, center)) = context.level_and_center() else {
+        return;
+    };
+    let grid = &tile_registry.grid;
+    let plan = context.config.plan(center, grid.chunk_coords(), |chunk| grid.contains_chunk(chunk));
+    if plan.is_empty() {
+        return;
+    }
+
+    // Props are children of their chunk and go with it
+    for (entity, chunk) in chunk_query.iter() {
+        if plan.unloaded.contains(&(chunk.x, chunk.z)) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for &chunk in &plan.unloaded {
+        for tile in tile_registry.grid.remove_chunk(chunk) {
+            commands.entity(tile).despawn();
+        }
+    }
+
+    for &chunk in &plan.loaded {
+        spawn_single_chunk_grid(
+            &mut commands,
+            &mut meshes,
+            &tile_types,
+            level,
+            &|coord| context.tile(level, coord),
+            chunk,
+        );
+    }
+    debug!("Streamed in chunks {:?}, out {:?}", plan.loaded, plan.unloaded);
+    streamed_writer.write(plan);
+}
+
+type StreamedTiles<'w, 's> =
+    Query<'w, 's, (Entity, &'static Tile, &'static Transform), Added<Tile>>;
+
+/// Adds the tiles of freshly streamed chunks to the `TileRegistry` and relinks neighbours
+/// across the borders of every chunk that came or went, without touching the rest.
+pub fn link_streamed_chunks(
+    mut streamed_events: MessageReader<ChunksStreamedEvent>,
+    mut tile_registry: ResMut<TileRegistry>,
+    mut tile_queries: ParamSet<(StreamedTiles, Query<&mut Tile>)>,
+) {
+    let streamed: Vec<(i32, i32)> = streamed_events
+        .read()
+        .flat_map(|event| event.loaded.iter().chain(&event.unloaded).copied())
+        .collect();
+    if streamed.is_empty() {
+        return;
+    }
+
+    let grid = &mut tile_registry.grid;
+    for (entity, tile, transform) in tile_queries.p0().iter() {
+        grid.insert(
+            (tile.x, tile.z),
+            entity,
+            transform.translation,
+            tile.walkable,
+            tile.movement_cost,
+        );
+    }
+    let mut tile_query = tile_queries.p1();
+    let linked: Vec<TileIndex> =
+        streamed.into_iter().flat_map(|chunk| grid.link_neighbors_around(chunk)).collect();
+    for index in linked {
+        let Some(Ok(mut tile)) = grid.entity(index).map(|entity| tile_query.get_mut(entity))
+        else {
+            continue;
+        };
+        let neighbors = grid.neighbors(index).map(|neighbor| grid.entity(neighbor));
+        if tile.neighbor_entities != neighbors {
+            tile.neighbor_entities = neighbors;
+        }
+    }
+    debug!("Tile registry holds {} tiles after streaming", grid.tile_count());
+}
@@ -1,74 +1,113 @@
-use crate::{materials::pavement, systems::plane_chunk_system::spawn_single_chunk_grid};
+use crate::{
+    components::{
+        PlaneChunk, chunk_streaming::ChunkStreamingConfig, level_plane::LevelProp,
+    },
+    level::{
+        Level, LevelAssets, level_asset::LevelTile, level_generator::world_tile,
+        tile_types::TileTypeRegistry,
+    },
+    shared::GameRng,
+    systems::{
+        chunk_streaming_system::StreamingContext, plane_chunk_system::spawn_single_chunk_grid,
+    },
+};
 use bevy::prelude::*;
 
-pub struct LevelPlaneConfig {
-    pub num_cols: i32,
-    pub num_rows: i32,
-    pub chunk_width: i32,
-    pub chunk_height: i32,
-    pub grid_size: i32,
-}
-
-impl Default for LevelPlaneConfig {
-    fn default() -> Self {
-        Self { num_cols: 3, num_rows: 3, chunk_width: 30, chunk_height: 30, grid_size: 15 }
-    }
-}
-
-pub fn spawn_default_chunk_grid(
+/// Spawns the chunks within streaming range of the player spawn; the rest of the world
+/// streams in as the player moves.
+pub fn spawn_level_chunk_grid(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut pavement_materials: ResMut<pavement::CheckedFloorMaterials>,
+    tile_types: Res<TileTypeRegistry>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    game_rng: Res<GameRng>,
+    streaming_config: Res<ChunkStreamingConfig>,
 ) {
-    let level_plane_config = LevelPlaneConfig::default();
+    let Some(level) = level_assets.current(&levels) else {
+        error!("Level asset is missing, nothing to spawn");
+        return;
+    };
 
+    let seed = game_rng.seed();
+    let chunks = ChunkStreamingConfig::chunks_around(
+        level.chunk_of(level.player_spawn()),
+        streaming_config.load_radius,
+    );
     spawn_chunk_grid(
         &mut commands,
         &mut meshes,
-        &mut pavement_materials,
-        level_plane_config.num_cols,
-        level_plane_config.num_rows,
-        level_plane_config.chunk_width,
-        level_plane_config.chunk_height,
-        level_plane_config.grid_size,
+        &tile_types,
+        level,
+        &|coord| world_tile(level, seed, coord),
+        &chunks,
     );
 }
 
-/// Spawns a grid of plane chunks arranged in columns and rows
+/// Spawns a placeholder mesh for every prop on newly spawned chunks, and respawns them on
+/// chunks the level editor touched. Props are children of their chunk, so they stream out
+/// with it.
+pub fn spawn_level_props(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut prop_assets: Local<Option<(Handle<Mesh>, Handle<StandardMaterial>)>>,
+    context: StreamingContext,
+    changed_chunks: Query<(Entity, &PlaneChunk, Option<&Children>), Changed<PlaneChunk>>,
+    prop_query: Query<(), With<LevelProp>>,
+) {
+    if changed_chunks.is_empty() {
+        return;
+    }
+    let Some(level) = context.level() else {
+        return;
+    };
+    let (mesh, material) = prop_assets.get_or_insert_with(|| {
+        (meshes.add(Cuboid::new(1.2, 1.2, 1.2)), materials.add(Color::srgb(0.45, 0.4, 0.35)))
+    });
+    for (chunk_entity, chunk, children) in changed_chunks.iter() {
+        for &child in children.into_iter().flatten().filter(|&&child| prop_query.contains(child)) {
+            commands.entity(child).despawn();
+        }
+        let on_chunk = |coord| level.chunk_of(coord) == (chunk.x, chunk.z);
+        let tile_size = chunk.width as f32 / chunk.grid_size as f32;
+        for prop in level.props.iter().filter(|prop| on_chunk(prop.coord)) {
+            // Offset of the tile centre from the chunk centre, resting on the tile
+            let local_x = prop.coord.0 - chunk.x * chunk.grid_size;
+            let local_z = prop.coord.1 - chunk.z * chunk.grid_size;
+            let position = Vec3::new(
+                (local_x as f32 + 0.5) * tile_size - chunk.width as f32 / 2.0,
+                context.tile(level, prop.coord).elevation + 0.6,
+                (local_z as f32 + 0.5) * tile_size - chunk.height as f32 / 2.0,
+            );
+            commands.entity(chunk_entity).with_child((
+                LevelProp,
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(position),
+                Name::new(format!("Prop {} {:?}", prop.name, prop.coord)),
+            ));
+        }
+    }
+}
+
+/// Spawns plane chunks at the given columns and rows
 ///
 /// # Arguments
-/// * `num_cols` - Number of columns in the grid
-/// * `num_rows` - Number of rows in the grid
-/// * `chunk_width` - Width of each individual chunk
-/// * `chunk_height` - Height of each individual chunk
-/// * `grid_size` - Number of tiles per chunk side
+/// * `tile_types` - Material, walkability and cost of each tile type
+/// * `tile_at` - Terrain and walkability of each global tile coordinate
+/// * `chunks` - Column and row of every chunk to spawn
 pub fn spawn_chunk_grid(
-    mut commands: &mut Commands,
-    mut meshes: &mut ResMut<Assets<Mesh>>,
-    mut pavement_materials: &mut ResMut<pavement::CheckedFloorMaterials>,
-    num_cols: i32,
-    num_rows: i32,
-    chunk_width: i32,
-    chunk_height: i32,
-    grid_size: i32,
+    commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    tile_types: &TileTypeRegistry,
+    level: &Level,
+    tile_at: &impl Fn((i32, i32)) -> LevelTile,
+    chunks: &[(i32, i32)],
 ) {
-    debug!("Spawning chunk grid: {}x{} chunks", num_cols, num_rows);
+    debug!("Spawning chunk grid: {} chunks", chunks.len());
 
-    for row in 0..num_rows {
-        for col in 0..num_cols {
-            // Calculate chunk position based on column and row
-            spawn_single_chunk_grid(
-                &mut commands,
-                &mut meshes,
-                &mut pavement_materials,
-                col,
-                row,
-                num_cols,
-                num_rows,
-                chunk_width,
-                chunk_height,
-                grid_size,
-            )
-        }
+    for &chunk in chunks {
+        spawn_single_chunk_grid(commands, meshes, tile_types, level, tile_at, chunk)
     }
 }
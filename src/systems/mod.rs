@@ -1,7 +1,10 @@
 pub mod camera_system;
+pub mod chunk_streaming_system;
 pub mod level_plane_system;
 pub mod movement;
 pub mod plane_chunk_system;
 pub mod player;
+pub mod simulation_system;
+pub mod tile_highlight_system;
 pub mod tile_selection_system;
 pub mod animation;
@@ -1,29 +1,17 @@
-use std::collections::{BinaryHeap, HashMap, HashSet};
-
-use crate::components::Tile;
 use crate::components::movements::a_star_movement::AStarNode;
+use crate::components::movements::search_scratch::with_search_scratch;
+use crate::components::movements::tile_graph::TileGraph;
 use bevy::prelude::*;
-use crate::shared::CharacterType;
-
-fn heuristic(pos1: Vec3, pos2: Vec3) -> f32 {
-    let dx = pos1.x - pos2.x;
-    let dy = pos1.y - pos2.y;
-    let dz = pos1.z - pos2.z;
-    dx * dx + dy * dy + dz * dz
-}
-
-pub fn astar_pathfind(
-    start: Entity,
-    goal: Entity,
-    tiles: &Query<(&Tile, &Transform), Without<CharacterType>>,
-) -> Option<Vec<Entity>> {
-    info! {"astart_pathfind start"}
 
-    let (start_tile, start_transform) = tiles.get(start).ok()?;
-    let (goal_tile, goal_transform) = tiles.get(goal).ok()?;
+pub fn astar_pathfind<G: TileGraph>(
+    graph: &G,
+    start: G::Node,
+    goal: G::Node,
+) -> Option<Vec<G::Node>> {
+    debug! {"astart_pathfind start"}
 
     // Check if start and goal are walkable
-    if !start_tile.walkable || !goal_tile.walkable {
+    if !graph.is_walkable(start) || !graph.is_walkable(goal) {
         info!("Pathfinding failed: Start or goal is not walkable");
         return None;
     }
@@ -33,102 +21,154 @@ pub fn astar_pathfind(
         return Some(vec![start]);
     }
 
-    let goal_pos = goal_transform.translation;
+    debug!("Starting A* from {:?} to {:?}", graph.coord(start), graph.coord(goal));
 
-    let mut open_set = BinaryHeap::new();
-    let mut closed_set = HashSet::new();
-    let mut g_scores = HashMap::new();
-    let mut came_from: HashMap<Entity, Entity> = HashMap::new();
+    with_search_scratch(|scratch| {
+        scratch.begin(graph.node_capacity());
+        let goal_index = graph.index(goal);
 
-    info!("Starting A* from {:?} to {:?}", start, goal);
-    g_scores.insert(start, 0.0);
+        let h_start = graph.heuristic(start, goal);
 
-    let h_start = heuristic(start_transform.translation, goal_pos);
+        scratch.record(graph.index(start), 0.0, None);
+        scratch.open_set.push(AStarNode {
+            node: graph.index(start),
+            f_score: h_start,
+            g_score: 0.0,
+        });
 
-    open_set.push(AStarNode { entity: start, f_score: h_start, g_score: 0.0 });
+        while let Some(current_node) = scratch.open_set.pop() {
+            let current_index = current_node.node;
 
-    while let Some(current_node) = open_set.pop() {
-        let current = current_node.entity;
-
-        // Goal reached
-        if current == goal {
-            return Some(reconstruct_path(&came_from, current));
-        }
+            // Goal reached
+            if current_index == goal_index {
+                let path = scratch.path_to(goal_index);
+                return Some(path.into_iter().map(|index| graph.node_at_index(index)).collect());
+            }
 
-        // Skip if already processed
-        if closed_set.contains(&current) {
-            continue;
-        }
-        closed_set.insert(current);
+            // Skip if already processed
+            if scratch.is_closed(current_index) {
+                continue;
+            }
+            scratch.close(current_index);
 
-        // Skip if we've found a better path already (handles duplicates in heap)
-        if current_node.g_score > *g_scores.get(&current).unwrap_or(&f32::INFINITY) {
-            continue;
-        }
+            // Skip if we've found a better path already (handles duplicates in heap)
+            if current_node.g_score > scratch.g_score(current_index) {
+                continue;
+            }
 
-        let (current_tile, current_tile_transform) = match tiles.get(current) {
-            Ok(tile) => tile,
-            Err(_) => continue,
-        };
+            let current = graph.node_at_index(current_index);
+            let current_g_score = current_node.g_score;
 
-        let current_pos = current_tile_transform.translation;
-        let current_g_score = current_node.g_score;
+            // Check all walkable neighbors
+            for neighbor in graph.neighbors(current) {
+                let neighbor_index = graph.index(neighbor);
 
-        // Check all neighbors
-        for maybe_neighbor in current_tile.neighbor_entities.iter() {
-            if let Some(neighbor) = maybe_neighbor {
                 // Skip if already in closed set
-                if closed_set.contains(&neighbor) {
-                    continue;
-                }
-
-                let (neighbor_tile, neighbour_tile_transform) = match tiles.get(*neighbor) {
-                    Ok(tile) => tile,
-                    Err(_) => continue,
-                };
-
-                // IMPORTANT: Skip non-walkable tiles
-                if !neighbor_tile.walkable {
+                if scratch.is_closed(neighbor_index) {
                     continue;
                 }
 
-                let neighbor_pos = neighbour_tile_transform.translation;
-                let edge_cost = heuristic(current_pos, neighbor_pos);
-                let tentative_g_score = current_g_score + edge_cost;
+                let tentative_g_score = current_g_score + graph.cost(current, neighbor);
 
                 // Only proceed if this path is better
-                let existing_g_score = g_scores.get(&neighbor).copied().unwrap_or(f32::INFINITY);
+                if tentative_g_score < scratch.g_score(neighbor_index) {
+                    scratch.record(neighbor_index, tentative_g_score, Some(current_index));
 
-                if tentative_g_score < existing_g_score {
-                    came_from.insert(*neighbor, current);
-                    g_scores.insert(*neighbor, tentative_g_score);
+                    let f_score = tentative_g_score + graph.heuristic(neighbor, goal);
 
-                    let h_score = heuristic(neighbor_pos, goal_pos);
-                    let f_score = tentative_g_score + h_score;
-
-                    open_set.push(AStarNode {
-                        entity: *neighbor,
+                    scratch.open_set.push(AStarNode {
+                        node: neighbor_index,
                         f_score,
                         g_score: tentative_g_score,
                     });
                 }
             }
         }
-    }
 
-    // No path found
-    info!("No path found");
-    None
+        // No path found
+        debug!("No path found");
+        None
+    })
 }
 
-fn reconstruct_path(came_from: &HashMap<Entity, Entity>, mut current: Entity) -> Vec<Entity> {
-    let mut path = vec![current];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::movements::grid_map::GridMap;
 
-    while let Some(&previous) = came_from.get(&current) {
-        current = previous;
-        path.push(current);
+    fn path_cost(map: &GridMap, path: &[(i32, i32)]) -> f32 {
+        path.windows(2).map(|step| map.cost(step[0], step[1])).sum()
     }
 
-    path.reverse();
-    path
+    #[test]
+    fn finds_straight_path_on_open_ground() {
+        let map = GridMap::from_ascii(
+            "
+            S....G
+            ",
+        );
+        let path = astar_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap());
+        assert_eq!(path.unwrap().len(), 6);
+    }
+
+    #[test]
+    fn walks_around_walls() {
+        let map = GridMap::from_ascii(
+            "
+            S.#...
+            ..#.#.
+            ..#.#G
+            ....#.
+            ",
+        );
+        let path =
+            astar_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).unwrap();
+        assert!(path.iter().all(|&tile| map.is_walkable(tile)));
+        // The only gap in the first wall, and the only one in the second
+        assert!(path.contains(&(2, 3)));
+        assert!(path.contains(&(4, 0)));
+        assert_eq!(path.last(), map.marker('G').as_ref());
+    }
+
+    #[test]
+    fn prefers_road_over_swamp() {
+        let map = GridMap::from_ascii(
+            "
+            ==========
+            S&&&&&&&&G
+            ",
+        );
+        let path =
+            astar_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).unwrap();
+        assert!(path.iter().any(|&(_, z)| z == 0));
+        let direct = (0..10).map(|x| (x, 1)).collect::<Vec<_>>();
+        assert!(path_cost(&map, &path) < path_cost(&map, &direct));
+    }
+
+    #[test]
+    fn climbs_around_steep_steps() {
+        let mut map = GridMap::from_ascii(
+            "
+            S...G
+            .....
+            ",
+        );
+        map.set_elevation((2, 0), 2.0); // Too steep to step onto from anywhere
+        map.set_elevation((3, 1), 0.5);
+        let path =
+            astar_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).unwrap();
+        assert!(!path.contains(&(2, 0)));
+        assert!(map.cost((2, 1), (3, 1)) > map.cost((3, 1), (2, 1)));
+    }
+
+    #[test]
+    fn returns_none_when_goal_is_walled_off() {
+        let map = GridMap::from_ascii(
+            "
+            S..#G
+            ...##
+            ",
+        );
+        assert!(astar_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).is_none());
+    }
 }
@@ -0,0 +1,145 @@
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use crate::components::movements::a_star_movement::AStarNode;
+use crate::components::movements::flow_field::{FlowField, ShortestPaths};
+use crate::components::movements::tile_graph::TileGraph;
+use crate::components::movements::tile_graph_snapshot::PathfindingSnapshot;
+use crate::components::{TileGrid, TilePosition};
+use crate::player::player::Player;
+
+/// Runs Dijkstra outwards from `source` over walkable tiles: each distance is the cost of
+/// walking from `source` to that tile.
+pub fn dijkstra<G: TileGraph>(graph: &G, source: G::Node) -> ShortestPaths {
+    search_from(graph, source, |current, neighbor| graph.cost(current, neighbor))
+}
+
+/// Runs Dijkstra backwards from `target`: each distance is the cost of walking from that tile
+/// to `target`, and each tile was reached from its next step towards it.
+pub fn reverse_dijkstra<G: TileGraph>(graph: &G, target: G::Node) -> ShortestPaths {
+    search_from(graph, target, |current, neighbor| graph.cost(neighbor, current))
+}
+
+fn search_from<G: TileGraph>(
+    graph: &G,
+    source: G::Node,
+    step_cost: impl Fn(G::Node, G::Node) -> f32,
+) -> ShortestPaths {
+    let mut paths = ShortestPaths::new(graph.node_capacity());
+    if !graph.is_walkable(source) {
+        return paths;
+    }
+
+    let mut open_set = BinaryHeap::new();
+    paths.distances[graph.index(source)] = 0.0;
+    paths.reached = 1;
+    open_set.push(AStarNode { node: graph.index(source), f_score: 0.0, g_score: 0.0 });
+
+    while let Some(current_node) = open_set.pop() {
+        let current_index = current_node.node;
+        if current_node.g_score > paths.distances[current_index] {
+            continue;
+        }
+
+        let current = graph.node_at_index(current_index);
+        for neighbor in graph.neighbors(current) {
+            let neighbor_index = graph.index(neighbor);
+            // Slopes are symmetric, so the neighbours found going out can also step back
+            let distance = current_node.g_score + step_cost(current, neighbor);
+            if distance < paths.distances[neighbor_index] {
+                if paths.distances[neighbor_index].is_infinite() {
+                    paths.reached += 1;
+                }
+                paths.distances[neighbor_index] = distance;
+                paths.came_from[neighbor_index] = current_index as u32;
+                open_set.push(AStarNode {
+                    node: neighbor_index,
+                    f_score: distance,
+                    g_score: distance,
+                });
+            }
+        }
+    }
+    paths
+}
+
+/// Builds the flow field towards `target`.
+///
+/// The search runs backwards from the target, so the tile a node was reached from is
+/// exactly the next step on its shortest path towards the target, uphill or down.
+pub fn build_flow_field(tiles: &Arc<TileGrid>, target: Entity) -> FlowField {
+    let paths = match tiles.index_of_entity(target) {
+        Some(index) => reverse_dijkstra(tiles.as_ref(), index),
+        None => ShortestPaths::default(),
+    };
+    FlowField { target_tile: Some(target), tiles: tiles.clone(), paths }
+}
+
+/// Rebuilds the shared flow field whenever the player steps onto a different tile
+/// or any tile changes (walkability or cost).
+pub fn update_flow_field(
+    mut flow_field: ResMut<FlowField>,
+    player_query: Query<&TilePosition, With<Player>>,
+    snapshot: Res<PathfindingSnapshot>,
+) {
+    let Ok(player_tile_position) = player_query.single() else {
+        return;
+    };
+    let Some(player_tile) = player_tile_position.tile else {
+        return;
+    };
+    if flow_field.target_tile == Some(player_tile) && !snapshot.is_changed() {
+        return;
+    }
+
+    *flow_field = build_flow_field(&snapshot.tiles, player_tile);
+    debug!("Rebuilt flow field with {} reachable tiles", flow_field.paths.reached);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::movements::grid_map::GridMap;
+
+    #[test]
+    fn every_step_leads_closer_to_the_source() {
+        let map = GridMap::from_ascii(
+            "
+            ....#....
+            .##.#.##.
+            .#..G..#.
+            .##.#.##.
+            ....#....
+            ",
+        );
+        let goal = map.marker('G').unwrap();
+        let paths = dijkstra(&map, goal);
+
+        assert_eq!(paths.distance(map.index(goal)), Some(0.0));
+        for index in 0..map.node_capacity() {
+            if let Some(next) = paths.came_from(index) {
+                assert!(paths.distances[next] < paths.distances[index]);
+            }
+        }
+        // Every walkable tile is connected to the goal
+        let walkable = (0..map.width)
+            .flat_map(|x| (0..map.height).map(move |z| (x, z)))
+            .filter(|&tile| map.is_walkable(tile))
+            .count();
+        assert_eq!(paths.reached, walkable);
+    }
+
+    #[test]
+    fn skips_unreachable_tiles() {
+        let map = GridMap::from_ascii(
+            "
+            G.#..
+            ..#..
+            ",
+        );
+        let paths = dijkstra(&map, map.marker('G').unwrap());
+        assert_eq!(paths.reached, 4);
+    }
+}
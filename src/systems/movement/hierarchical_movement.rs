@@ -0,0 +1,675 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use crate::components::movements::a_star_movement::AStarNode;
+use crate::components::movements::grid_map::GridMap;
+use crate::components::movements::hierarchical::{
+    Cluster, ClusterCoord, ClusterGraph, ClusterView, TileCoord,
+};
+use crate::components::movements::tile_graph::TileGraph;
+use crate::components::chunk_streaming::ChunksStreamedEvent;
+use crate::components::{Tile, TileGrid, TileRegistry};
+use crate::systems::movement::a_star_movement::astar_pathfind;
+use crate::components::movements::flow_field::ShortestPaths;
+use crate::systems::movement::flow_field::{dijkstra, reverse_dijkstra};
+
+/// Border segments longer than this get an entrance at each end instead of one in the middle.
+const MAX_SINGLE_ENTRANCE_LENGTH: usize = 6;
+
+/// Cost between `start` and every tile reachable without leaving `cluster`, as a lookup.
+/// `search` is `dijkstra` for costs from `start`, `reverse_dijkstra` for costs to it.
+fn cluster_dijkstra<'a>(
+    graph: &'a ClusterGraph,
+    start: TileCoord,
+    cluster: ClusterCoord,
+    search: fn(&ClusterView<'a>, TileCoord) -> ShortestPaths,
+) -> impl Fn(TileCoord) -> Option<f32> + 'a {
+    let view = ClusterView::new(graph, cluster);
+    let paths = search(&view, start);
+    move |tile| view.node_at(tile).and_then(|tile| paths.distance(view.index(tile)))
+}
+
+/// Finds entrance pairs along the border between two adjacent clusters. Diagonal
+/// neighbours only touch at a corner, which is an entrance when it can be crossed.
+fn compute_border(
+    graph: &ClusterGraph,
+    low: ClusterCoord,
+    high: ClusterCoord,
+) -> Vec<(TileCoord, TileCoord)> {
+    let size = graph.cluster_size;
+    if low.0 != high.0 && low.1 != high.1 {
+        // `low` is south of `high`, the corner tiles sit on either side of the shared corner
+        let (low_x, high_x) = if high.0 > low.0 {
+            (high.0 * size - 1, high.0 * size)
+        } else {
+            (low.0 * size, low.0 * size - 1)
+        };
+        let (a, b) = ((low_x, high.1 * size - 1), (high_x, high.1 * size));
+        return if graph.is_walkable(a) && graph.tiles.can_step(a, b) {
+            vec![(a, b)]
+        } else {
+            Vec::new()
+        };
+    }
+    let facing_pair = |i: i32| -> (TileCoord, TileCoord) {
+        if low.1 == high.1 {
+            // Vertical border: `low` is west of `high`
+            let x = high.0 * size;
+            ((x - 1, low.1 * size + i), (x, low.1 * size + i))
+        } else {
+            // Horizontal border: `low` is south of `high`
+            let z = high.1 * size;
+            ((low.0 * size + i, z - 1), (low.0 * size + i, z))
+        }
+    };
+
+    let mut entrances = Vec::new();
+    let mut segment: Vec<(TileCoord, TileCoord)> = Vec::new();
+    for i in 0..=size {
+        let open = i < size && {
+            let (a, b) = facing_pair(i);
+            graph.is_walkable(a) && graph.tiles.can_step(a, b)
+        };
+        if open {
+            segment.push(facing_pair(i));
+            continue;
+        }
+        if segment.is_empty() {
+            continue;
+        }
+        if segment.len() > MAX_SINGLE_ENTRANCE_LENGTH {
+            entrances.push(segment[0]);
+            entrances.push(segment[segment.len() - 1]);
+        } else {
+            entrances.push(segment[segment.len() / 2]);
+        }
+        segment.clear();
+    }
+    entrances
+}
+
+/// Recomputes the intra-cluster edges between all entrances of `cluster`.
+fn compute_cluster(graph: &ClusterGraph, cluster: ClusterCoord) -> Cluster {
+    let entrances = graph.entrances(cluster);
+    let mut edges = HashMap::new();
+    for &entrance in &entrances {
+        let distances = cluster_dijkstra(graph, entrance, cluster, dijkstra);
+        let reachable = entrances
+            .iter()
+            .filter(|&&other| other != entrance)
+            .filter_map(|&other| distances(other).map(|cost| (other, cost)))
+            .collect();
+        edges.insert(entrance, reachable);
+    }
+    Cluster { edges }
+}
+
+/// Recomputes the borders of `cluster` and every cluster whose entrances changed as a result.
+pub fn refresh_cluster(graph: &mut ClusterGraph, cluster: ClusterCoord) {
+    let mut dirty = vec![cluster];
+    for (low, high) in ClusterGraph::border_keys(cluster) {
+        if !graph.clusters.contains_key(&low) || !graph.clusters.contains_key(&high) {
+            continue;
+        }
+        let border = compute_border(graph, low, high);
+        if graph.borders.get(&(low, high)) != Some(&border) {
+            dirty.push(if low == cluster { high } else { low });
+        }
+        graph.borders.insert((low, high), border);
+    }
+    for cluster in dirty {
+        let computed = compute_cluster(graph, cluster);
+        graph.clusters.insert(cluster, computed);
+    }
+}
+
+/// Builds the full abstract graph from tile walkability and movement costs.
+pub fn build_cluster_graph_from(tiles: GridMap, cluster_size: i32) -> ClusterGraph {
+    let mut graph = ClusterGraph { cluster_size, tiles, ..Default::default() };
+
+    let cluster_coords: HashSet<ClusterCoord> = (0..graph.tiles.node_capacity())
+        .map(|index| graph.cluster_of(graph.tiles.node_at_index(index)))
+        .collect();
+    for &cluster in &cluster_coords {
+        graph.clusters.insert(cluster, Cluster::default());
+    }
+    for &cluster in &cluster_coords {
+        for (low, high) in ClusterGraph::border_keys(cluster) {
+            if low == cluster && cluster_coords.contains(&high) {
+                let border = compute_border(&graph, low, high);
+                graph.borders.insert((low, high), border);
+            }
+        }
+    }
+    for &cluster in &cluster_coords {
+        let computed = compute_cluster(&graph, cluster);
+        graph.clusters.insert(cluster, computed);
+    }
+    graph
+}
+
+/// Searches the abstract entrance graph, temporarily linking start and goal into it.
+fn abstract_search(
+    graph: &ClusterGraph,
+    start: TileCoord,
+    goal: TileCoord,
+) -> Option<Vec<TileCoord>> {
+    let start_cluster = graph.cluster_of(start);
+    let goal_cluster = graph.cluster_of(goal);
+
+    let start_distances = cluster_dijkstra(graph, start, start_cluster, dijkstra);
+    let mut start_edges: Vec<(TileCoord, f32)> = graph
+        .entrances(start_cluster)
+        .into_iter()
+        .filter_map(|entrance| start_distances(entrance).map(|cost| (entrance, cost)))
+        .collect();
+    // Standing on an entrance, the way across its border starts right here
+    start_edges.extend(
+        graph.inter_edges(start).into_iter().map(|other| (other, graph.cost(start, other))),
+    );
+    let goal_distances = cluster_dijkstra(graph, goal, goal_cluster, reverse_dijkstra);
+
+    let mut open_set = BinaryHeap::new();
+    let mut closed_set = HashSet::new();
+    let mut g_scores = HashMap::new();
+    let mut came_from = HashMap::new();
+
+    g_scores.insert(start, 0.0);
+    open_set.push(AStarNode { node: start, f_score: graph.heuristic(start, goal), g_score: 0.0 });
+
+    while let Some(current_node) = open_set.pop() {
+        let current = current_node.node;
+        if current == goal {
+            return Some(reconstruct_path(&came_from, current));
+        }
+        if !closed_set.insert(current) {
+            continue;
+        }
+
+        let mut edges: Vec<(TileCoord, f32)> = if current == start {
+            start_edges.clone()
+        } else {
+            let cluster = graph.cluster_of(current);
+            let mut edges = graph
+                .clusters
+                .get(&cluster)
+                .and_then(|c| c.edges.get(&current))
+                .cloned()
+                .unwrap_or_default();
+            edges.extend(
+                graph
+                    .inter_edges(current)
+                    .into_iter()
+                    .map(|other| (other, graph.cost(current, other))),
+            );
+            edges
+        };
+        if graph.cluster_of(current) == goal_cluster
+            && let Some(cost) = goal_distances(current)
+        {
+            edges.push((goal, cost));
+        }
+
+        for (neighbor, cost) in edges {
+            if closed_set.contains(&neighbor) {
+                continue;
+            }
+            let tentative_g_score = current_node.g_score + cost;
+            if tentative_g_score < g_scores.get(&neighbor).copied().unwrap_or(f32::INFINITY) {
+                came_from.insert(neighbor, current);
+                g_scores.insert(neighbor, tentative_g_score);
+                open_set.push(AStarNode {
+                    node: neighbor,
+                    f_score: tentative_g_score + graph.heuristic(neighbor, goal),
+                    g_score: tentative_g_score,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn reconstruct_path(came_from: &HashMap<TileCoord, TileCoord>, mut current: TileCoord) -> Vec<TileCoord> {
+    let mut path = vec![current];
+
+    while let Some(&previous) = came_from.get(&current) {
+        current = previous;
+        path.push(current);
+    }
+
+    path.reverse();
+    path
+}
+
+/// Turns an abstract path into a tile path by refining each hop inside its cluster.
+fn refine_path(graph: &ClusterGraph, abstract_path: &[TileCoord]) -> Option<Vec<TileCoord>> {
+    let mut path = vec![*abstract_path.first()?];
+    for hop in abstract_path.windows(2) {
+        let (from, to) = (hop[0], hop[1]);
+        let from_cluster = graph.cluster_of(from);
+        if from == to {
+            continue;
+        }
+        if from_cluster != graph.cluster_of(to) {
+            path.push(to);
+            continue;
+        }
+        let segment = astar_pathfind(&ClusterView::new(graph, from_cluster), from, to)?;
+        path.extend(segment.into_iter().skip(1));
+    }
+    Some(path)
+}
+
+/// HPA* search between two tiles.
+///
+/// Tiles in the same chunk are searched directly inside that chunk first; otherwise the
+/// abstract graph is searched and only the chunks on the abstract path are refined.
+/// Nothing ever searches the whole grid.
+pub fn hierarchical_pathfind<G: TileGraph>(
+    tiles: &G,
+    graph: &ClusterGraph,
+    start: G::Node,
+    goal: G::Node,
+) -> Option<Vec<G::Node>> {
+    if graph.cluster_size <= 0 {
+        warn!("Cluster graph not built yet");
+        return None;
+    }
+    let start_coord = tiles.coord(start);
+    let goal_coord = tiles.coord(goal);
+
+    if !graph.is_walkable(start_coord) || !graph.is_walkable(goal_coord) {
+        info!("Pathfinding failed: Start or goal is not walkable");
+        return None;
+    }
+
+    let start_cluster = graph.cluster_of(start_coord);
+    let goal_cluster = graph.cluster_of(goal_coord);
+    let same_cluster_path = if start_cluster == goal_cluster {
+        astar_pathfind(&ClusterView::new(graph, start_cluster), start_coord, goal_coord)
+    } else {
+        None
+    };
+
+    let coords = same_cluster_path
+        .or_else(|| {
+            abstract_search(graph, start_coord, goal_coord)
+                .and_then(|abstract_path| refine_path(graph, &abstract_path))
+        })?;
+
+    coords.into_iter().map(|coord| tiles.node_at(coord)).collect()
+}
+
+/// Builds the abstract graph over every tile in `grid`, one cluster per chunk.
+pub fn cluster_graph_from_grid(grid: &TileGrid) -> Option<ClusterGraph> {
+    let coords = || grid.iter().map(|(coord, _)| coord);
+    let (Some(min_x), Some(min_z), Some(max_x), Some(max_z)) = (
+        coords().map(|(x, _)| x).min(),
+        coords().map(|(_, z)| z).min(),
+        coords().map(|(x, _)| x).max(),
+        coords().map(|(_, z)| z).max(),
+    ) else {
+        return None;
+    };
+    let mut tiles = GridMap::spanning((min_x, min_z), (max_x, max_z));
+    for (coord, _) in grid.iter() {
+        copy_tile(&mut tiles, grid, coord);
+    }
+    Some(build_cluster_graph_from(tiles, grid.chunk_size))
+}
+
+/// Copies a tile of `grid` into the cluster graph's own grid, in tile units. Tiles missing
+/// from `grid` are blocked.
+fn copy_tile(tiles: &mut GridMap, grid: &TileGrid, coord: TileCoord) {
+    let Some(index) = grid.index_of(coord) else {
+        tiles.set_walkable(coord, false);
+        tiles.set_movement_cost(coord, 1.0);
+        tiles.set_elevation(coord, 0.0);
+        return;
+    };
+    tiles.set_walkable(coord, grid.is_walkable(index));
+    tiles.set_movement_cost(coord, grid.movement_cost(index));
+    tiles.set_elevation(coord, grid.position(index).y / grid.tile_size);
+}
+
+/// Adds the clusters of `loaded` chunks and drops those of `unloaded` ones, recomputing
+/// only the borders and clusters next to them. `grid` must already hold the new tiles.
+fn stream_clusters(
+    graph: &mut ClusterGraph,
+    grid: &TileGrid,
+    loaded: &[ClusterCoord],
+    unloaded: &[ClusterCoord],
+) {
+    let size = graph.cluster_size;
+    let chunks = || grid.chunk_coords();
+    let (Some(min_x), Some(min_z), Some(max_x), Some(max_z)) = (
+        chunks().map(|(x, _)| x).min(),
+        chunks().map(|(_, z)| z).min(),
+        chunks().map(|(x, _)| x).max(),
+        chunks().map(|(_, z)| z).max(),
+    ) else {
+        *graph = ClusterGraph { cluster_size: size, ..Default::default() };
+        return;
+    };
+    // The graph's grid only spans the loaded chunks
+    graph
+        .tiles
+        .resize((min_x * size, min_z * size), ((max_x + 1) * size - 1, (max_z + 1) * size - 1));
+
+    let chunk_tiles = |(cx, cz): ClusterCoord| {
+        (0..size * size).map(move |i| (cx * size + i % size, cz * size + i / size))
+    };
+    let mut dirty = HashSet::new();
+    for &chunk in unloaded {
+        graph.clusters.remove(&chunk);
+        for key in ClusterGraph::border_keys(chunk) {
+            if graph.borders.remove(&key).is_some() {
+                dirty.insert(if key.0 == chunk { key.1 } else { key.0 });
+            }
+        }
+        for coord in chunk_tiles(chunk) {
+            copy_tile(&mut graph.tiles, grid, coord);
+        }
+    }
+    let loaded: Vec<ClusterCoord> =
+        loaded.iter().copied().filter(|&chunk| grid.contains_chunk(chunk)).collect();
+    for &chunk in &loaded {
+        for coord in chunk_tiles(chunk) {
+            copy_tile(&mut graph.tiles, grid, coord);
+        }
+        graph.clusters.insert(chunk, Cluster::default());
+    }
+    for &chunk in &loaded {
+        dirty.insert(chunk);
+        for (low, high) in ClusterGraph::border_keys(chunk) {
+            if graph.clusters.contains_key(&low) && graph.clusters.contains_key(&high) {
+                let border = compute_border(graph, low, high);
+                graph.borders.insert((low, high), border);
+                dirty.insert(if low == chunk { high } else { low });
+            }
+        }
+    }
+    for cluster in dirty {
+        if graph.clusters.contains_key(&cluster) {
+            let computed = compute_cluster(graph, cluster);
+            graph.clusters.insert(cluster, computed);
+        }
+    }
+}
+
+pub fn build_cluster_graph(mut commands: Commands, tile_registry: Res<TileRegistry>) {
+    let Some(graph) = cluster_graph_from_grid(&tile_registry.grid) else {
+        warn!("No tiles found, skipping cluster graph");
+        return;
+    };
+    info!(
+        "Built cluster graph with {} clusters and {} borders",
+        graph.clusters.len(),
+        graph.borders.len()
+    );
+    commands.insert_resource(graph);
+}
+
+/// Updates the abstract graph after chunks streamed in or out, once their tiles are linked
+/// into the `TileRegistry`. Only the streamed chunks and their neighbours are recomputed.
+pub fn update_streamed_cluster_graph(
+    mut streamed_events: MessageReader<ChunksStreamedEvent>,
+    tile_registry: Res<TileRegistry>,
+    mut graph: ResMut<ClusterGraph>,
+) {
+    let (mut loaded, mut unloaded) = (Vec::new(), Vec::new());
+    for event in streamed_events.read() {
+        loaded.extend_from_slice(&event.loaded);
+        unloaded.extend_from_slice(&event.unloaded);
+    }
+    if loaded.is_empty() && unloaded.is_empty() {
+        return;
+    }
+    let grid = &tile_registry.grid;
+    if graph.cluster_size != grid.chunk_size {
+        // Not built yet
+        if let Some(built) = cluster_graph_from_grid(grid) {
+            *graph = built;
+        }
+        return;
+    }
+    stream_clusters(&mut graph, grid, &loaded, &unloaded);
+    debug!("Cluster graph holds {} clusters after streaming", graph.clusters.len());
+}
+
+/// Keeps the abstract graph in sync with `Tile.walkable` and `Tile.movement_cost`,
+/// refreshing only the affected chunks.
+pub fn update_cluster_graph(
+    mut graph: ResMut<ClusterGraph>,
+    changed_tiles: Query<&Tile, Changed<Tile>>,
+) {
+    if graph.cluster_size <= 0 {
+        return;
+    }
+    let mut dirty_clusters = HashSet::new();
+    for tile in changed_tiles.iter() {
+        let coord = (tile.x, tile.z);
+        if !graph.tiles.contains(coord)
+            || (graph.tiles.is_walkable(coord) == tile.walkable
+                && graph.tiles.movement_cost(coord) == tile.movement_cost)
+        {
+            continue;
+        }
+        graph.tiles.set_walkable(coord, tile.walkable);
+        graph.tiles.set_movement_cost(coord, tile.movement_cost);
+        dirty_clusters.insert(graph.cluster_of(coord));
+    }
+    for cluster in dirty_clusters {
+        debug!("Refreshing cluster {:?}", cluster);
+        refresh_cluster(&mut graph, cluster);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path_cost(map: &GridMap, path: &[TileCoord]) -> f32 {
+        path.windows(2).map(|step| map.cost(step[0], step[1])).sum()
+    }
+
+    /// Three by three clusters of four tiles, walls and swamp spread across their borders
+    const MAZE: &str = "
+        S...#.......
+        ....#..&&&..
+        .####..&&&..
+        ......##.#..
+        ..#.....%#..
+        ..#..:::.#..
+        ..####.......
+        ....#...###.
+        .~~.#.......
+        .~~...##.#..
+        .##...#..#G.
+        ....#...#...
+        ";
+
+    #[test]
+    fn places_entrances_on_open_border_segments() {
+        let map = GridMap::from_ascii(
+            "
+            ...#....
+            ...#....
+            ........
+            ...#....
+            ",
+        );
+        let graph = build_cluster_graph_from(map, 4);
+        // Rows 0, 1 and 3 are walled off on the west side of the border
+        assert_eq!(graph.borders[&((0, 0), (1, 0))], vec![((3, 2), (4, 2))]);
+        // The open top edge of the two clusters has no neighbour to connect to
+        assert_eq!(graph.borders.len(), 1);
+        assert_eq!(graph.entrances((1, 0)), vec![(4, 2)]);
+
+        let open = build_cluster_graph_from(GridMap::new(16, 8, 1.0), 8);
+        // A long open segment gets an entrance at each end
+        assert_eq!(open.borders[&((0, 0), (1, 0))], vec![((7, 0), (8, 0)), ((7, 7), (8, 7))]);
+    }
+
+    #[test]
+    fn matches_astar_across_clusters() {
+        let map = GridMap::from_ascii(MAZE);
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        let graph = build_cluster_graph_from(map.clone(), 4);
+
+        let abstract_path = abstract_search(&graph, start, goal).unwrap();
+        assert_eq!((abstract_path[0], abstract_path[abstract_path.len() - 1]), (start, goal));
+        let refined = refine_path(&graph, &abstract_path).unwrap();
+        assert!(refined.windows(2).all(|step| map.can_step(step[0], step[1])));
+
+        let hierarchical = hierarchical_pathfind(&map, &graph, start, goal).unwrap();
+        let optimal = astar_pathfind(&map, start, goal).unwrap();
+        let (cost, optimal_cost) = (path_cost(&map, &hierarchical), path_cost(&map, &optimal));
+        assert!(cost >= optimal_cost - 1e-3);
+        // Entrances are fixed points on each border, so HPA* may take a small detour
+        assert!(cost <= optimal_cost * 1.2, "{cost} vs {optimal_cost}");
+    }
+
+    #[test]
+    fn crosses_chunk_corners_without_searching_the_whole_grid() {
+        // The only way on is diagonally across the corner where four clusters meet
+        let map = GridMap::from_ascii(
+            "
+            S..#####
+            ...#####
+            ...#####
+            ###.####
+            ####....
+            ####....
+            ####....
+            ####...G
+            ",
+        );
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        let graph = build_cluster_graph_from(map.clone(), 4);
+        let path = hierarchical_pathfind(&map, &graph, start, goal).unwrap();
+        assert!(path.contains(&(3, 3)) && path.contains(&(4, 4)));
+        assert!(path.windows(2).all(|step| map.can_step(step[0], step[1])));
+    }
+
+    #[test]
+    fn crosses_chunk_corners_between_distant_clusters() {
+        // Three by three clusters, linked only across the corners of the middle one
+        let map = GridMap::from_ascii(
+            "
+            S...########
+            ....########
+            ....########
+            ....########
+            ####....####
+            ####....####
+            ####....####
+            ####....####
+            ########....
+            ########....
+            ########....
+            ########...G
+            ",
+        );
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        let graph = build_cluster_graph_from(map.clone(), 4);
+        assert_eq!(graph.borders[&((0, 0), (1, 1))], vec![((3, 3), (4, 4))]);
+
+        let path = hierarchical_pathfind(&map, &graph, start, goal).unwrap();
+        assert!([(3, 3), (4, 4), (7, 7), (8, 8)].iter().all(|tile| path.contains(tile)));
+        assert!(path.windows(2).all(|step| map.can_step(step[0], step[1])));
+        let optimal = astar_pathfind(&map, start, goal).unwrap();
+        assert!((path_cost(&map, &path) - path_cost(&map, &optimal)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn leaves_from_an_entrance_it_stands_on() {
+        // The start is the only entrance of the western cluster
+        let map = GridMap::from_ascii(
+            "
+            ...#....
+            ...S....
+            ...#....
+            ...#..G.
+            ",
+        );
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        let graph = build_cluster_graph_from(map.clone(), 4);
+        assert_eq!(graph.entrances((0, 0)), vec![start]);
+
+        let path = hierarchical_pathfind(&map, &graph, start, goal).unwrap();
+        assert!(path.windows(2).all(|step| map.can_step(step[0], step[1])));
+        let optimal = astar_pathfind(&map, start, goal).unwrap();
+        assert!((path_cost(&map, &path) - path_cost(&map, &optimal)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn refreshing_a_cluster_matches_a_full_rebuild() {
+        let mut map = GridMap::from_ascii(MAZE);
+        let mut graph = build_cluster_graph_from(map.clone(), 4);
+        // One tile on a border, closing an entrance, and one inside a cluster
+        for (coord, walkable) in [((7, 3), true), ((4, 4), false), ((5, 5), false)] {
+            map.set_walkable(coord, walkable);
+            graph.tiles.set_walkable(coord, walkable);
+            let cluster = graph.cluster_of(coord);
+            refresh_cluster(&mut graph, cluster);
+
+            let rebuilt = build_cluster_graph_from(map.clone(), 4);
+            assert_eq!(graph.borders, rebuilt.borders, "after changing {coord:?}");
+            assert_eq!(graph.clusters, rebuilt.clusters, "after changing {coord:?}");
+        }
+    }
+
+    #[test]
+    fn streaming_chunks_matches_a_full_rebuild() {
+        let map = GridMap::from_ascii(MAZE);
+        let insert_chunk = |grid: &mut TileGrid, (cx, cz): ClusterCoord| {
+            for index in 0..16 {
+                let coord = (cx * 4 + index % 4, cz * 4 + index / 4);
+                let entity = Entity::from_raw_u32((coord.1 * 12 + coord.0 + 1) as u32).unwrap();
+                let position = Vec3::new(coord.0 as f32, 0.0, coord.1 as f32);
+                let (walkable, cost) = (map.is_walkable(coord), map.movement_cost(coord));
+                grid.insert(coord, entity, position, walkable, cost);
+            }
+        };
+        let mut grid = TileGrid::new(4, 1.0);
+        for chunk in [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)] {
+            insert_chunk(&mut grid, chunk);
+        }
+        let mut graph = cluster_graph_from_grid(&grid).unwrap();
+
+        let loaded = [(2, 0), (2, 1), (2, 2)];
+        for chunk in loaded {
+            insert_chunk(&mut grid, chunk);
+        }
+        stream_clusters(&mut graph, &grid, &loaded, &[]);
+        let rebuilt = cluster_graph_from_grid(&grid).unwrap();
+        assert_eq!(graph.borders, rebuilt.borders, "after loading");
+        assert_eq!(graph.clusters, rebuilt.clusters, "after loading");
+
+        let unloaded = [(0, 0), (0, 1), (0, 2)];
+        for chunk in unloaded {
+            grid.remove_chunk(chunk);
+        }
+        stream_clusters(&mut graph, &grid, &[], &unloaded);
+        let rebuilt = cluster_graph_from_grid(&grid).unwrap();
+        assert_eq!(graph.borders, rebuilt.borders, "after unloading");
+        assert_eq!(graph.clusters, rebuilt.clusters, "after unloading");
+    }
+
+    #[test]
+    fn returns_none_when_goal_is_walled_off() {
+        let map = GridMap::from_ascii(
+            "
+            S.......#...
+            ........#...
+            ........#.G.
+            ........#...
+            ",
+        );
+        let graph = build_cluster_graph_from(map.clone(), 4);
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        assert!(hierarchical_pathfind(&map, &graph, start, goal).is_none());
+    }
+}
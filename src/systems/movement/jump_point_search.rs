@@ -0,0 +1,285 @@
+use bevy::prelude::*;
+
+use crate::components::NEIGHBOR_DIRECTIONS;
+use crate::components::movements::a_star_movement::AStarNode;
+use crate::components::movements::hierarchical::TileCoord;
+use crate::components::movements::search_scratch::with_search_scratch;
+use crate::components::movements::tile_graph::TileGraph;
+
+fn walkable_at<G: TileGraph>(graph: &G, coord: TileCoord) -> Option<G::Node> {
+    graph.node_at(coord).filter(|&node| graph.is_walkable(node))
+}
+
+/// Whether `coord` breaks up the open ground around `from`: blocked, or at another
+/// elevation. Either way the optimal path may have to bend around it.
+fn blocked<G: TileGraph>(graph: &G, from: TileCoord, coord: TileCoord) -> bool {
+    let elevation = |coord| graph.node_at(coord).map(|node| graph.position(node).y);
+    walkable_at(graph, coord).is_none() || elevation(coord) != elevation(from)
+}
+
+/// Whether moving through `(x, z)` in `direction` reveals a neighbour that can only be
+/// reached optimally through this tile (a "forced" neighbour).
+fn has_forced_neighbor<G: TileGraph>(graph: &G, (x, z): TileCoord, (dx, dz): TileCoord) -> bool {
+    let forced = |obstacle: TileCoord, neighbor: TileCoord| {
+        blocked(graph, (x, z), obstacle) && walkable_at(graph, neighbor).is_some()
+    };
+    match (dx, dz) {
+        (dx, 0) => forced((x, z + 1), (x + dx, z + 1)) || forced((x, z - 1), (x + dx, z - 1)),
+        (0, dz) => forced((x + 1, z), (x + 1, z + dz)) || forced((x - 1, z), (x - 1, z + dz)),
+        (dx, dz) => forced((x - dx, z), (x - dx, z + dz)) || forced((x, z - dz), (x + dx, z - dz)),
+    }
+}
+
+/// Directions worth exploring from a jump point reached by travelling in `direction`.
+fn pruned_directions<G: TileGraph>(
+    graph: &G,
+    (x, z): TileCoord,
+    direction: TileCoord,
+) -> Vec<TileCoord> {
+    let mut directions = vec![direction];
+    match direction {
+        (dx, 0) => {
+            for side in [1, -1] {
+                if blocked(graph, (x, z), (x, z + side)) {
+                    directions.push((dx, side));
+                }
+            }
+        }
+        (0, dz) => {
+            for side in [1, -1] {
+                if blocked(graph, (x, z), (x + side, z)) {
+                    directions.push((side, dz));
+                }
+            }
+        }
+        (dx, dz) => {
+            directions.push((dx, 0));
+            directions.push((0, dz));
+            if blocked(graph, (x, z), (x - dx, z)) {
+                directions.push((-dx, dz));
+            }
+            if blocked(graph, (x, z), (x, z - dz)) {
+                directions.push((dx, -dz));
+            }
+        }
+    }
+    directions
+}
+
+/// Walks from `from` in `direction` until it hits a jump point, returning it with the cost
+/// of getting there. Terrain boundaries and changes in elevation count as jump points,
+/// because pruning is only exact while the cost of a step stays uniform.
+fn jump<G: TileGraph>(
+    graph: &G,
+    from: G::Node,
+    (dx, dz): TileCoord,
+    goal: G::Node,
+) -> Option<(G::Node, f32)> {
+    let mut current = from;
+    let mut cost = 0.0;
+    loop {
+        let (x, z) = graph.coord(current);
+        let next_coord = (x + dx, z + dz);
+        let next = walkable_at(graph, next_coord).filter(|&next| graph.can_step(current, next))?;
+        cost += graph.cost(current, next);
+
+        if next == goal
+            || !same_ground(graph, current, next)
+            || has_forced_neighbor(graph, next_coord, (dx, dz))
+        {
+            return Some((next, cost));
+        }
+        // A diagonal step is a jump point when a straight jump from it finds one
+        if dx != 0
+            && dz != 0
+            && (jump(graph, next, (dx, 0), goal).is_some()
+                || jump(graph, next, (0, dz), goal).is_some())
+        {
+            return Some((next, cost));
+        }
+
+        current = next;
+    }
+}
+
+/// Equal terrain cost and elevation, so steps between the two cost the same as on open ground.
+fn same_ground<G: TileGraph>(graph: &G, a: G::Node, b: G::Node) -> bool {
+    graph.movement_cost(a) == graph.movement_cost(b) && graph.position(a).y == graph.position(b).y
+}
+
+/// Jump Point Search over the tile grid.
+///
+/// Expands only jump points instead of every neighbour, which pays off on large open maps.
+/// On uniform-cost terrain the paths are as short as A*'s; where terrain costs change,
+/// every boundary tile becomes a jump point and is expanded in all directions.
+pub fn jps_pathfind<G: TileGraph>(
+    graph: &G,
+    start: G::Node,
+    goal: G::Node,
+) -> Option<Vec<G::Node>> {
+    if !graph.is_walkable(start) || !graph.is_walkable(goal) {
+        info!("Pathfinding failed: Start or goal is not walkable");
+        return None;
+    }
+    if start == goal {
+        return Some(vec![start]);
+    }
+
+    with_search_scratch(|scratch| {
+        scratch.begin(graph.node_capacity());
+        let goal_index = graph.index(goal);
+
+        scratch.record(graph.index(start), 0.0, None);
+        scratch.open_set.push(AStarNode {
+            node: graph.index(start),
+            f_score: graph.heuristic(start, goal),
+            g_score: 0.0,
+        });
+
+        while let Some(current_node) = scratch.open_set.pop() {
+            let current_index = current_node.node;
+            if current_index == goal_index {
+                let jump_points: Vec<G::Node> = scratch
+                    .path_to(goal_index)
+                    .into_iter()
+                    .map(|index| graph.node_at_index(index))
+                    .collect();
+                return Some(expand_path(graph, &jump_points));
+            }
+            if current_node.g_score > scratch.g_score(current_index)
+                || scratch.is_closed(current_index)
+            {
+                continue;
+            }
+            scratch.close(current_index);
+
+            let current = graph.node_at_index(current_index);
+            let coord = graph.coord(current);
+            let directions = match scratch.came_from(current_index).map(|i| graph.node_at_index(i))
+            {
+                Some(parent) if same_ground(graph, parent, current) => {
+                    let parent_coord = graph.coord(parent);
+                    let direction =
+                        ((coord.0 - parent_coord.0).signum(), (coord.1 - parent_coord.1).signum());
+                    pruned_directions(graph, coord, direction)
+                }
+                _ => NEIGHBOR_DIRECTIONS.to_vec(),
+            };
+
+            for direction in directions {
+                let Some((jump_point, jump_cost)) = jump(graph, current, direction, goal) else {
+                    continue;
+                };
+                let jump_index = graph.index(jump_point);
+                if scratch.is_closed(jump_index) {
+                    continue;
+                }
+                let tentative_g_score = current_node.g_score + jump_cost;
+                if tentative_g_score < scratch.g_score(jump_index) {
+                    scratch.record(jump_index, tentative_g_score, Some(current_index));
+                    scratch.open_set.push(AStarNode {
+                        node: jump_index,
+                        f_score: tentative_g_score + graph.heuristic(jump_point, goal),
+                        g_score: tentative_g_score,
+                    });
+                }
+            }
+        }
+
+        info!("No path found");
+        None
+    })
+}
+
+/// Turns the chain of jump points back into a tile-by-tile path.
+fn expand_path<G: TileGraph>(graph: &G, jump_points: &[G::Node]) -> Vec<G::Node> {
+    let mut path = vec![jump_points[0]];
+    for leg in jump_points.windows(2) {
+        let (mut x, mut z) = graph.coord(leg[0]);
+        let end = graph.coord(leg[1]);
+        let (dx, dz) = ((end.0 - x).signum(), (end.1 - z).signum());
+        while (x, z) != end {
+            x += dx;
+            z += dz;
+            path.extend(graph.node_at((x, z)));
+        }
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::movements::grid_map::GridMap;
+    use crate::systems::movement::a_star_movement::astar_pathfind;
+
+    fn path_cost(map: &GridMap, path: &[TileCoord]) -> f32 {
+        path.windows(2).map(|step| map.cost(step[0], step[1])).sum()
+    }
+
+    fn assert_matches_astar(map: &GridMap) {
+        let (start, goal) = (map.marker('S').unwrap(), map.marker('G').unwrap());
+        let jps = jps_pathfind(map, start, goal).unwrap();
+        let astar = astar_pathfind(map, start, goal).unwrap();
+
+        for step in jps.windows(2) {
+            assert!((step[0].0 - step[1].0).abs() <= 1 && (step[0].1 - step[1].1).abs() <= 1);
+        }
+        assert!(jps.iter().all(|&tile| map.is_walkable(tile)));
+        assert_eq!((jps.first(), jps.last()), (Some(&start), Some(&goal)));
+        assert!((path_cost(map, &jps) - path_cost(map, &astar)).abs() < 0.001);
+    }
+
+    #[test]
+    fn matches_astar_on_open_ground() {
+        let map = GridMap::from_ascii(
+            "
+            S.........
+            ..........
+            ..........
+            .........G
+            ",
+        );
+        assert_matches_astar(&map);
+    }
+
+    #[test]
+    fn matches_astar_through_maze() {
+        let map = GridMap::from_ascii(
+            "
+            S..#......
+            .#.#.####.
+            .#...#....
+            .#####.##.
+            ......#..G
+            ",
+        );
+        assert_matches_astar(&map);
+    }
+
+    #[test]
+    fn matches_astar_around_scattered_rubble() {
+        let map = GridMap::from_ascii(
+            "
+            S...#.....#...
+            ..#....#......
+            .....#....#.#.
+            .#.......#....
+            ...#..#......G
+            ",
+        );
+        assert_matches_astar(&map);
+    }
+
+    #[test]
+    fn returns_none_when_goal_is_walled_off() {
+        let map = GridMap::from_ascii(
+            "
+            S....###
+            .....#G#
+            .....###
+            ",
+        );
+        assert!(jps_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).is_none());
+    }
+}
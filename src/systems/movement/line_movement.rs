@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+
+use crate::components::movements::tile_graph::TileGraph;
+
+/// Rasterizes a straight line between two tile coordinates (Bresenham).
+///
+/// Consecutive coordinates are always 8-connected, which matches the neighbourhood
+/// stored in `Tile.neighbor_entities`, so the result can be walked tile by tile.
+pub fn bresenham_line(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut z) = start;
+    let dx = (end.0 - start.0).abs();
+    let dz = -(end.1 - start.1).abs();
+    let step_x = if start.0 < end.0 { 1 } else { -1 };
+    let step_z = if start.1 < end.1 { 1 } else { -1 };
+    let mut error = dx + dz;
+
+    let mut line = Vec::with_capacity((dx.max(-dz) + 1) as usize);
+    loop {
+        line.push((x, z));
+        if (x, z) == end {
+            break;
+        }
+        let doubled_error = 2 * error;
+        if doubled_error >= dz {
+            error += dz;
+            x += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            z += step_z;
+        }
+    }
+    line
+}
+
+/// Builds a straight-line path between two tiles.
+///
+/// Returns `None` when any tile on the line is missing, not walkable or too steep, so callers
+/// can fall back to a full search.
+pub fn line_pathfind<G: TileGraph>(
+    graph: &G,
+    start: G::Node,
+    goal: G::Node,
+) -> Option<Vec<G::Node>> {
+    let mut path: Vec<G::Node> = Vec::new();
+    for coord in bresenham_line(graph.coord(start), graph.coord(goal)) {
+        let node = graph.node_at(coord)?;
+        let reachable = match path.last() {
+            Some(&previous) => graph.can_step(previous, node),
+            None => graph.is_walkable(node),
+        };
+        if !reachable {
+            debug!("Straight line blocked at ({}, {})", coord.0, coord.1);
+            return None;
+        }
+        path.push(node);
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::movements::grid_map::GridMap;
+
+    #[test]
+    fn line_steps_are_eight_connected() {
+        let line = bresenham_line((0, 0), (7, -3));
+        assert_eq!(line.first(), Some(&(0, 0)));
+        assert_eq!(line.last(), Some(&(7, -3)));
+        for step in line.windows(2) {
+            assert!((step[0].0 - step[1].0).abs() <= 1 && (step[0].1 - step[1].1).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn follows_clear_line() {
+        let map = GridMap::from_ascii(
+            "
+            S.....
+            ......
+            .....G
+            ",
+        );
+        let path = line_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap());
+        assert_eq!(path.unwrap().len(), 6);
+    }
+
+    #[test]
+    fn gives_up_on_blocked_line() {
+        let map = GridMap::from_ascii(
+            "
+            S..#..G
+            ",
+        );
+        assert!(line_pathfind(&map, map.marker('S').unwrap(), map.marker('G').unwrap()).is_none());
+    }
+}
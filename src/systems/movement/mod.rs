@@ -1,2 +1,13 @@
 pub mod a_star_movement;
+pub mod flow_field;
+pub mod hierarchical_movement;
+pub mod jump_point_search;
+pub mod line_movement;
 pub mod movement_system;
+pub mod path_invalidation;
+pub mod path_smoothing;
+pub mod path_request_system;
+pub mod reservation_system;
+pub mod steering_system;
+pub mod terrain_system;
+pub mod waypoint_system;
@@ -1,9 +1,16 @@
-use std::collections::VecDeque;
-
-use crate::components::movements::movement::{MoveRequestEvent, Movement, MovementSpeed, MovementType};
-use crate::player::player::Player;
-use crate::components::{MovementState, Tile, TilePosition, TileSelectedEvent};
-use crate::systems::movement::a_star_movement::astar_pathfind;
+use crate::components::movements::movement::{MoveRequestEvent, Movement, MovementSpeed};
+use crate::components::movements::path_request::{
+    PathRequest, PathRequestQueue, PathTask, PathfindingConfig,
+};
+use crate::components::movements::reservation::TileReservations;
+use crate::components::movements::steering::Steering;
+use crate::components::movements::waypoints::{WaypointAction, WaypointEvent};
+use crate::systems::movement::path_smoothing::{curve_length, curve_point, curve_tangent};
+use crate::systems::movement::reservation_system::{find_sidestep, nearest_free_neighbor};
+use crate::player::player::{Player, RunMode};
+use crate::components::{
+    MovementState, Tile, TileGrid, TilePosition, TileRegistry, TileSelectedEvent,
+};
 use bevy::prelude::*;
 use crate::shared::CharacterType;
 
@@ -17,16 +24,40 @@ pub fn init_player_movement(
     }
 }
 
+/// F3 switches the search used for the player's moves.
+pub fn cycle_player_movement_type(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut config: ResMut<PathfindingConfig>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        config.player_movement_type = config.player_movement_type.next();
+        info!("Player pathfinding: {:?}", config.player_movement_type);
+    }
+}
+
+/// Moves the player to the clicked tile, or queues it as a waypoint on shift-click.
+/// A plain click drops any queued waypoints.
 pub fn tile_selected_event_handle(
     mut tile_selected_events: MessageReader<TileSelectedEvent>,
-    player_query: Query<Entity, With<Player>>,
+    mut player_query: Query<(Entity, &mut RunMode), With<Player>>,
     mut player_move_events: MessageWriter<MoveRequestEvent>,
+    mut waypoint_events: MessageWriter<WaypointEvent>,
+    config: Res<PathfindingConfig>,
 ) {
-    if let Ok(entity) = player_query.single() {
+    if let Ok((entity, mut run_mode)) = player_query.single_mut() {
         for event in tile_selected_events.read() {
+            if event.append {
+                waypoint_events.write(WaypointEvent {
+                    entity,
+                    action: WaypointAction::Append(event.target_tile_entity),
+                });
+                continue;
+            }
+            waypoint_events.write(WaypointEvent { entity, action: WaypointAction::Clear });
+            run_mode.requested = event.run;
             player_move_events.write(MoveRequestEvent {
                 entity,
-                movement_type: MovementType::ASTAR,
+                movement_type: config.player_movement_type,
                 source_tile_entity: event.source_tile_entity,
                 target_tile_entity: event.target_tile_entity,
             });
@@ -36,20 +67,41 @@ pub fn tile_selected_event_handle(
     }
 }
 
+/// Queues path requests; the search itself runs asynchronously in `dispatch_path_requests`.
+/// A new request from an entity supersedes its queued or in-flight one.
 pub fn movement_request_handler(
+    mut commands: Commands,
     mut move_events: MessageReader<MoveRequestEvent>,
-    mut character_query: Query<(&mut Transform, &mut Movement, &mut TilePosition, &mut MovementState), With<CharacterType>>,
-    tiles: Query<(&Tile, &Transform), Without<CharacterType>>,
+    mut character_query: Query<(&Transform, &Movement, &mut TilePosition, Option<&PathTask>), With<CharacterType>>,
+    mut path_request_queue: ResMut<PathRequestQueue>,
+    tile_registry: Res<TileRegistry>,
+    reservations: Res<TileReservations>,
 ) {
     for event in move_events.read() {
-        if let Ok((transform, mut player_movement, mut tile_position, mut movement_state)) = character_query.get_mut(event.entity) {
+        if let Ok((transform, player_movement, mut tile_position, path_task)) = character_query.get_mut(event.entity) {
             if tile_position.tile.is_none() {
                 tile_position.tile = Some(event.source_tile_entity);
             }
 
-            if !player_movement.path.is_empty() {
+            let mut target_tile_entity = event.target_tile_entity;
+            if reservations.holder(target_tile_entity).is_some_and(|holder| holder != event.entity)
+                && let Some(free_tile) = nearest_free_neighbor(event.entity, target_tile_entity, transform.translation, &tile_registry.grid, &reservations)
+            {
+                info!("Target tile is occupied, moving next to it instead");
+                target_tile_entity = free_tile;
+            }
+
+            let pending_target = path_request_queue
+                .pending_target(event.entity)
+                .or(path_task.map(|task| task.target_tile_entity));
+            if let Some(pending_target) = pending_target {
+                if pending_target == target_tile_entity {
+                    info!("Path to this tile already requested, ignoring duplicate click");
+                    continue;
+                }
+            } else if !player_movement.path.is_empty() {
                 if let Some(current_target) = player_movement.path.back() {
-                    if *current_target == event.target_tile_entity {
+                    if *current_target == target_tile_entity {
                         info!("Already moving to this tile, ignoring duplicate click");
                         continue;
                     }
@@ -57,88 +109,235 @@ pub fn movement_request_handler(
                 info!("Interrupting current path for new destination");
             }
 
-            if let Some(tile_entity) = tile_position.tile {
-
-                let paths = match event.movement_type {
-                    MovementType::ASTAR => astar_pathfind(tile_entity, event.target_tile_entity, &tiles),
-                    MovementType::SHORTEST => None
-                };
-
-                if let Some(path) = paths {
-                    info!("New path found with {} steps", path.len());
-
-                    player_movement.path = VecDeque::from(path);
-                    player_movement.segment_start = transform.translation; // Start from current position
-                    player_movement.translation_progress = 0.0;
-                    player_movement.target_transform = None; // THIS IS THE KEY LINE FOR INTERRUPTION
-                    player_movement.segment_distance = 0.0;
-
-                    *movement_state = MovementState::Walking;
-
-                } else {
-                    warn!("No path found to target tile");
-                }
+            if path_task.is_some() {
+                commands.entity(event.entity).remove::<PathTask>();
             }
+            path_request_queue.push(PathRequest {
+                entity: event.entity,
+                movement_type: event.movement_type,
+                target_tile_entity,
+            });
         } else {
             warn!("No player found to execute movement");
         }
     }
 }
 
+/// How long a character waits for an occupied tile before trying to walk around it
+const SIDESTEP_DELAY: f32 = 0.4;
+/// Segments a character may finish within a single tick
+const MAX_SEGMENTS_PER_TICK: usize = 4;
+
+type MovingCharacter = (
+    Entity,
+    &'static mut Transform,
+    &'static MovementSpeed,
+    &'static mut Movement,
+    &'static mut TilePosition,
+    &'static mut MovementState,
+    &'static CharacterType,
+    Option<&'static Steering>,
+);
+
 pub fn update_player_movement(
-    query: Query<(&mut Transform, &mut MovementSpeed, &mut Movement, &mut TilePosition, &mut MovementState, &CharacterType)>,
-    transforms: Query<&Transform, Without<MovementSpeed>>,
+    query: Query<MovingCharacter>,
+    tiles: Query<(&Transform, &Tile), Without<CharacterType>>,
+    tile_registry: Res<TileRegistry>,
+    mut reservations: ResMut<TileReservations>,
     time: Res<Time>,
 ) {
-    for (mut transform, speed, mut movement, mut tile_position, mut movement_state, character_type) in query {
-        if movement.target_transform.is_none() && !movement.path.is_empty() {
-            if let Some(next_entity) = movement.path.pop_front() {
-                if let Ok(target) = transforms.get(next_entity) {
-                    movement.segment_start = transform.translation; // ✅ Save current position
-                    movement.target_transform = Some(*target);
-                    movement.translation_progress = 0.0;
-                    tile_position.tile = Some(next_entity); // Update current tile
-
-                    movement.segment_distance =
-                        movement.segment_start.distance(target.translation);
-
-                    // ✅ ROTATION: Face the target
-                    let direction = target.translation - movement.segment_start;
-                    if direction.length_squared() > 0.001 {
-                        // Look at target (assumes Y-up, character faces Z-forward)
-                        transform.look_to(-direction.normalize(), Vec3::Y);
+    let delta = time.delta_secs();
+    for (entity, mut transform, speed, mut movement, mut tile_position, mut movement_state, character_type, steering) in query {
+        // Path following works on the anchor; the steering offset is re-applied on top
+        let steering_offset = steering.map_or(Vec3::ZERO, |steering| steering.offset);
+
+        // 1. Accelerate towards the terrain's top speed, braking ahead of the destination.
+        // Costly terrain (sand, water, ...) slows the walk onto the tile being entered
+        if movement.target_transform.is_none() && movement.path.is_empty() {
+            movement.current_speed = 0.0;
+        } else {
+            let movement_cost = tile_position
+                .tile
+                .and_then(|tile| tiles.get(tile).ok())
+                .map_or(1.0, |(_, tile)| tile.movement_cost);
+            let top_speed = speed.top_speed(&movement_state) / movement_cost;
+            let horizon = speed.braking_distance(top_speed) + top_speed * delta;
+            let position = transform.translation - steering_offset;
+            let remaining_distance = remaining_path_distance(&movement, position, &tiles, horizon);
+            movement.current_speed =
+                speed.next_speed(movement.current_speed, top_speed, remaining_distance, delta);
+        }
+
+        // 2. Spend this tick's distance, moving on to the next segments without stopping
+        let mut distance_left = movement.current_speed * delta;
+        let mut heading = None;
+        for _ in 0..MAX_SEGMENTS_PER_TICK {
+            // Reserve the next tile before stepping; wait or sidestep while it is taken
+            if movement.target_transform.is_none() {
+                let Some(&next_entity) = movement.path.front() else {
+                    break;
+                };
+                if !reservations.try_reserve(next_entity, entity) {
+                    movement.current_speed = 0.0;
+                    movement.wait_time += delta;
+                    if movement.wait_time >= SIDESTEP_DELAY
+                        && let Some(current_tile) = tile_position.tile
+                    {
+                        wait_or_sidestep(
+                            entity,
+                            current_tile,
+                            &mut movement,
+                            &tile_registry.grid,
+                            &reservations,
+                        );
                     }
+                    break;
                 }
+                movement.wait_time = 0.0;
+                let position = transform.translation - steering_offset;
+                start_next_segment(&mut movement, &mut tile_position, position, &tiles);
             }
-        }
+            let Some(target) = movement.target_transform else {
+                break;
+            };
+
+            // Follow the curve from segment_start around the target tile to segment_end
+            let distance_to_end = movement.segment_distance * (1.0 - movement.translation_progress);
+            if distance_left < distance_to_end {
+                movement.translation_progress += distance_left / movement.segment_distance;
+                distance_left = 0.0;
+            } else {
+                movement.translation_progress = 1.0;
+                distance_left -= distance_to_end;
+            }
+            let (start, control, end) =
+                (movement.segment_start, target.translation, movement.segment_end);
+            let progress = movement.translation_progress;
+            transform.translation = curve_point(start, control, end, progress) + steering_offset;
+            heading = Some(curve_tangent(start, control, end, progress));
 
-        // 2. Check if translation_progress >= 1.0
-        if movement.translation_progress >= 1.0 {
-            // Set target to None (will pop next element on next frame)
+            if movement.translation_progress < 1.0 {
+                break;
+            }
+            // A path cut short mid-curve still has to finish on the tile centre
+            if movement.path.is_empty()
+                && movement.segment_end.distance_squared(target.translation) > 0.0001
+            {
+                movement.segment_start = movement.segment_end;
+                movement.segment_end = target.translation;
+                movement.segment_distance = movement.segment_start.distance(target.translation);
+                movement.translation_progress = 0.0;
+                continue;
+            }
             movement.target_transform = None;
-            continue;
         }
 
-        // 3. Move toward target
-        if let Some(target) = movement.target_transform {
-            let movement_this_frame = speed.speed * time.delta_secs();
-            let progress_increment = if movement.segment_distance > 0.0 {
-                movement_this_frame / movement.segment_distance
-            } else {
-                1.0 // Instant movement for zero distance
-            };
+        // 3. ROTATION: Turn towards the direction of travel at a limited rate
+        if let Some(direction) = heading.map(|heading| heading.with_y(0.0))
+            && direction.length_squared() > 0.001
+        {
+            // Character faces Z-forward, so look away from the direction of travel
+            let facing = Transform::default().looking_to(-direction.normalize(), Vec3::Y).rotation;
+            transform.rotation = transform.rotation.rotate_towards(facing, speed.turn_rate * delta);
+        }
+
+        if character_type == &CharacterType::Player
+            && movement.path.is_empty()
+            && movement.target_transform.is_none()
+            && *movement_state != MovementState::Idle
+        {
+            *movement_state = MovementState::Idle;
+        }
+    }
+}
+
+/// Pops the next tile off the path and sets up the curve towards it.
+fn start_next_segment(
+    movement: &mut Movement,
+    tile_position: &mut TilePosition,
+    position: Vec3,
+    tiles: &Query<(&Transform, &Tile), Without<CharacterType>>,
+) {
+    let Some(next_entity) = movement.path.pop_front() else {
+        return;
+    };
+    let Ok((target, _)) = tiles.get(next_entity) else {
+        return;
+    };
+    movement.segment_start = position;
+    movement.target_transform = Some(*target);
+    movement.translation_progress = 0.0;
+    tile_position.tile = Some(next_entity); // Update current tile
 
-            movement.translation_progress += progress_increment;
-            movement.translation_progress = movement.translation_progress.min(1.0);
+    // Round the corner: end halfway to the following tile, or exactly on the last one
+    movement.segment_end = movement
+        .path
+        .front()
+        .and_then(|&following| tiles.get(following).ok())
+        .map_or(target.translation, |(following, _)| {
+            target.translation.midpoint(following.translation)
+        });
+    movement.segment_distance =
+        curve_length(movement.segment_start, target.translation, movement.segment_end);
+}
 
-            // ✅ Lerp from segment_start to target
-            transform.translation = movement
-                .segment_start
-                .lerp(target.translation, movement.translation_progress);
+/// Distance left along the path, counted only up to `horizon`.
+fn remaining_path_distance(
+    movement: &Movement,
+    position: Vec3,
+    tiles: &Query<(&Transform, &Tile), Without<CharacterType>>,
+    horizon: f32,
+) -> f32 {
+    let (mut remaining, mut last_point) = match movement.target_transform {
+        Some(target) => {
+            let on_segment = movement.segment_distance * (1.0 - movement.translation_progress);
+            // The settle back onto the tile centre at the end of a cut-short path
+            let settle = if movement.path.is_empty() {
+                movement.segment_end.distance(target.translation)
+            } else {
+                0.0
+            };
+            (on_segment + settle, movement.segment_end)
+        }
+        None => (0.0, position),
+    };
+    for &tile in &movement.path {
+        if remaining > horizon {
+            break;
         }
+        let Ok((tile_transform, _)) = tiles.get(tile) else {
+            break;
+        };
+        remaining += last_point.distance(tile_transform.translation);
+        last_point = tile_transform.translation;
+    }
+    remaining
+}
 
-        if character_type == &CharacterType::Player &&movement.path.is_empty() && *movement_state != MovementState::Idle {
-            *movement_state = MovementState::Idle;
+/// Routes around a character blocking the next tile. If the blocked tile is the destination
+/// itself, the character stops where it is instead.
+fn wait_or_sidestep(
+    entity: Entity,
+    current_tile: Entity,
+    movement: &mut Movement,
+    grid: &TileGrid,
+    reservations: &TileReservations,
+) {
+    let Some(&blocked) = movement.path.front() else {
+        return;
+    };
+    let Some(&rejoin) = movement.path.get(1) else {
+        info!("Destination tile is occupied, stopping next to it");
+        movement.path.clear();
+        return;
+    };
+
+    if let Some(sidestep) = find_sidestep(entity, current_tile, blocked, rejoin, grid, reservations) {
+        if sidestep == rejoin {
+            movement.path.pop_front();
+        } else {
+            movement.path[0] = sidestep;
         }
+        movement.wait_time = 0.0;
     }
 }
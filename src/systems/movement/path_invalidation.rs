@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::components::movements::movement::{
+    MoveRequestEvent, Movement, MovementType, PathNotFoundEvent,
+};
+use crate::components::chunk_streaming::ChunksStreamedEvent;
+use crate::components::movements::path_request::PathfindingConfig;
+use crate::components::{Tile, TilePosition, TileRegistry};
+use crate::player::player::Player;
+
+/// Replans every in-flight path that crosses a tile which just became non-walkable.
+///
+/// The path is cut right before the first blocked tile, so the character stops short of it
+/// if no alternative exists. The step already in progress is finished, since `TilePosition`
+/// has already moved onto that tile. The replan uses the search that planned the path.
+pub fn invalidate_blocked_paths(
+    changed_tiles: Query<(Entity, &Tile), Changed<Tile>>,
+    tiles: Query<&Tile>,
+    config: Res<PathfindingConfig>,
+    mut characters: Query<(Entity, &mut Movement, &TilePosition, Has<Player>)>,
+    mut move_request_writer: MessageWriter<MoveRequestEvent>,
+    mut path_not_found_writer: MessageWriter<PathNotFoundEvent>,
+) {
+    let blocked: HashSet<Entity> =
+        changed_tiles.iter().filter(|(_, tile)| !tile.walkable).map(|(entity, _)| entity).collect();
+    if blocked.is_empty() {
+        return;
+    }
+
+    for (entity, mut movement, tile_position, is_player) in characters.iter_mut() {
+        let Some(blocked_index) = movement.path.iter().position(|tile| blocked.contains(tile))
+        else {
+            continue;
+        };
+        let Some(&destination) = movement.path.back() else {
+            continue;
+        };
+        movement.path.truncate(blocked_index);
+
+        let destination_walkable = tiles.get(destination).is_ok_and(|tile| tile.walkable);
+        match tile_position.tile {
+            Some(current_tile) if destination_walkable => {
+                info!("Path of {:?} is blocked, replanning", entity);
+                let fallback =
+                    if is_player { config.player_movement_type } else { MovementType::ASTAR };
+                move_request_writer.write(MoveRequestEvent {
+                    movement_type: movement.movement_type.unwrap_or(fallback),
+                    entity,
+                    source_tile_entity: current_tile,
+                    target_tile_entity: destination,
+                });
+            }
+            _ => {
+                info!("Destination of {:?} is no longer reachable", entity);
+                path_not_found_writer
+                    .write(PathNotFoundEvent { entity, target_tile_entity: destination });
+            }
+        }
+    }
+}
+
+/// Cuts paths short of tiles whose chunk just streamed out, and gives up on reaching them.
+pub fn drop_unloaded_paths(
+    mut streamed_events: MessageReader<ChunksStreamedEvent>,
+    tile_registry: Res<TileRegistry>,
+    mut characters: Query<(Entity, &mut Movement)>,
+    mut path_not_found_writer: MessageWriter<PathNotFoundEvent>,
+) {
+    if streamed_events.read().filter(|event| !event.unloaded.is_empty()).count() == 0 {
+        return;
+    }
+
+    for (entity, mut movement) in characters.iter_mut() {
+        let unloaded = |tile: &Entity| tile_registry.grid.index_of_entity(*tile).is_none();
+        let Some(unloaded_index) = movement.path.iter().position(unloaded) else {
+            continue;
+        };
+        let Some(&destination) = movement.path.back() else {
+            continue;
+        };
+        movement.path.truncate(unloaded_index);
+        info!("Path of {:?} runs into an unloaded chunk, stopping short", entity);
+        path_not_found_writer.write(PathNotFoundEvent { entity, target_tile_entity: destination });
+    }
+}
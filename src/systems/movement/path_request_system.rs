@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, block_on};
+
+use crate::components::movements::hierarchical::ClusterGraph;
+use crate::components::movements::movement::{Movement, MovementType, PathNotFoundEvent};
+use crate::components::movements::path_request::{PathRequestQueue, PathTask, PathfindingConfig};
+use crate::components::movements::tile_graph_snapshot::PathfindingSnapshot;
+use crate::components::{
+    MovementState, SimulationTick, Tile, TileGrid, TilePosition, TileRegistry,
+};
+use crate::systems::movement::a_star_movement::astar_pathfind;
+use crate::systems::movement::hierarchical_movement::hierarchical_pathfind;
+use crate::systems::movement::jump_point_search::jps_pathfind;
+use crate::systems::movement::line_movement::line_pathfind;
+use crate::systems::movement::path_smoothing::smooth_path;
+
+/// Runs the search selected by `movement_type`. Safe to call from any thread.
+pub fn find_path(
+    movement_type: MovementType,
+    start: Entity,
+    goal: Entity,
+    tiles: &TileGrid,
+    clusters: &ClusterGraph,
+) -> Option<Vec<Entity>> {
+    let (start, goal) = (tiles.index_of_entity(start)?, tiles.index_of_entity(goal)?);
+    let path = match movement_type {
+        MovementType::ASTAR => {
+            astar_pathfind(tiles, start, goal).map(|path| smooth_path(tiles, path))
+        }
+        MovementType::SHORTEST => line_pathfind(tiles, start, goal).or_else(|| {
+            info!("Straight line blocked, falling back to A*");
+            astar_pathfind(tiles, start, goal)
+        }),
+        MovementType::HPASTAR => {
+            hierarchical_pathfind(tiles, clusters, start, goal).map(|path| smooth_path(tiles, path))
+        }
+        MovementType::JPS => jps_pathfind(tiles, start, goal).map(|path| smooth_path(tiles, path)),
+    }?;
+    path.into_iter().map(|index| tiles.entity(index)).collect()
+}
+
+/// Mirrors tile changes into the `TileRegistry` grid and refreshes the snapshots handed
+/// to pathfinding tasks.
+pub fn update_pathfinding_snapshot(
+    mut snapshot: ResMut<PathfindingSnapshot>,
+    mut tile_registry: ResMut<TileRegistry>,
+    changed_tiles: Query<(Entity, &Tile), Changed<Tile>>,
+    cluster_graph: Res<ClusterGraph>,
+) {
+    let mut changed = false;
+    let grid = &mut tile_registry.bypass_change_detection().grid;
+    for (entity, tile) in changed_tiles.iter() {
+        if let Some(index) = grid.index_of_entity(entity) {
+            changed |= grid.set_state(index, tile.walkable, tile.movement_cost);
+        }
+    }
+    if changed {
+        tile_registry.set_changed();
+    }
+    if tile_registry.is_changed() {
+        debug!("Rebuilt tile graph snapshot with {} tiles", tile_registry.grid.tile_count());
+        snapshot.tiles = Arc::new(tile_registry.grid.clone());
+    }
+    if cluster_graph.is_changed() {
+        snapshot.clusters = Arc::new(cluster_graph.clone());
+    }
+}
+
+/// Starts queued path requests on the `AsyncComputeTaskPool`, up to the per-tick budget.
+pub fn dispatch_path_requests(
+    mut commands: Commands,
+    mut path_request_queue: ResMut<PathRequestQueue>,
+    config: Res<PathfindingConfig>,
+    snapshot: Res<PathfindingSnapshot>,
+    tick: Res<SimulationTick>,
+    tile_positions: Query<&TilePosition>,
+) {
+    let task_pool = AsyncComputeTaskPool::get();
+    let mut started = 0;
+    while started < config.max_requests_per_tick {
+        let Some(request) = path_request_queue.requests.pop_front() else {
+            break;
+        };
+        // Plan from wherever the character is now, not where it was when it asked
+        let Some(start) =
+            tile_positions.get(request.entity).ok().and_then(|position| position.tile)
+        else {
+            continue;
+        };
+
+        let tiles = snapshot.tiles.clone();
+        let clusters = snapshot.clusters.clone();
+        let movement_type = request.movement_type;
+        let goal = request.target_tile_entity;
+        let task = task_pool
+            .spawn(async move { find_path(movement_type, start, goal, &tiles, &clusters) });
+
+        commands.entity(request.entity).try_insert(PathTask {
+            target_tile_entity: request.target_tile_entity,
+            movement_type,
+            task,
+            ready_tick: tick.0 + config.result_delay_ticks,
+        });
+        started += 1;
+    }
+}
+
+/// Applies searches to their character's `Movement` on their `ready_tick`.
+///
+/// A search still running at its deadline is waited for, so every result lands on the same
+/// tick whatever the machine's speed.
+pub fn apply_path_results(
+    mut commands: Commands,
+    mut path_not_found_writer: MessageWriter<PathNotFoundEvent>,
+    tile_registry: Res<TileRegistry>,
+    tick: Res<SimulationTick>,
+    mut characters: Query<(
+        Entity,
+        &mut PathTask,
+        &Transform,
+        &mut Movement,
+        &TilePosition,
+        &mut MovementState,
+    )>,
+) {
+    for (entity, mut path_task, transform, mut movement, tile_position, mut movement_state) in
+        characters.iter_mut()
+    {
+        if tick.0 < path_task.ready_tick {
+            continue;
+        }
+        let result = block_on(&mut path_task.task);
+        commands.entity(entity).remove::<PathTask>();
+
+        let Some(path) = result else {
+            warn!("No path found to target tile");
+            path_not_found_writer.write(PathNotFoundEvent {
+                entity,
+                target_tile_entity: path_task.target_tile_entity,
+            });
+            continue;
+        };
+        info!("New path found with {} steps", path.len());
+
+        let mut path = VecDeque::from(path);
+        // The character may have stepped further while the search ran
+        if let Some(index) = path.iter().position(|&tile| Some(tile) == tile_position.tile) {
+            path.drain(..index);
+        }
+        // Chunks may have streamed out since the search started
+        let unloaded = |tile: &Entity| tile_registry.grid.index_of_entity(*tile).is_none();
+        if let Some(index) = path.iter().position(unloaded) {
+            path.truncate(index);
+        }
+
+        movement.path = path;
+        movement.movement_type = Some(path_task.movement_type);
+        movement.segment_start = transform.translation; // Start from current position
+        movement.translation_progress = 0.0;
+        movement.target_transform = None; // Interrupts the current segment
+        movement.segment_distance = 0.0;
+
+        // A running character keeps running onto the new path
+        if *movement_state == MovementState::Idle {
+            *movement_state = MovementState::Walking;
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+use crate::components::movements::tile_graph::TileGraph;
+use crate::systems::movement::line_movement::bresenham_line;
+
+/// Cost of walking `path` tile by tile, or `None` if any tile is blocked or too steep.
+fn path_cost<G: TileGraph>(graph: &G, path: &[G::Node]) -> Option<f32> {
+    let mut cost = 0.0;
+    for step in path.windows(2) {
+        if !graph.can_step(step[0], step[1]) {
+            return None;
+        }
+        cost += graph.cost(step[0], step[1]);
+    }
+    Some(cost)
+}
+
+/// Straight run of tiles between two tiles, if every tile on it exists.
+fn straight_leg<G: TileGraph>(graph: &G, from: G::Node, to: G::Node) -> Option<Vec<G::Node>> {
+    bresenham_line(graph.coord(from), graph.coord(to))
+        .into_iter()
+        .map(|coord| graph.node_at(coord))
+        .collect()
+}
+
+/// String-pulls a tile path: redundant corners are dropped whenever a straight line between
+/// the surrounding waypoints stays on walkable tiles and costs no more than the original detour.
+///
+/// The result is still a dense list of 8-connected tiles, so occupancy and `TilePosition`
+/// keep working tile by tile; only the staircase pattern of grid searches disappears.
+pub fn smooth_path<G: TileGraph>(graph: &G, path: Vec<G::Node>) -> Vec<G::Node> {
+    if path.len() < 3 {
+        return path;
+    }
+
+    let mut smoothed = vec![path[0]];
+    let mut anchor_index = 0;
+    let mut best_leg = path[..2].to_vec();
+    let mut index = 2;
+    while index < path.len() {
+        let original_cost = path_cost(graph, &path[anchor_index..=index]);
+        let leg = straight_leg(graph, path[anchor_index], path[index]);
+        let shortcut = match (leg, original_cost) {
+            (Some(leg), Some(original_cost)) => {
+                path_cost(graph, &leg).filter(|&cost| cost <= original_cost + 0.001).map(|_| leg)
+            }
+            _ => None,
+        };
+
+        match shortcut {
+            Some(leg) => {
+                best_leg = leg;
+                index += 1;
+            }
+            None => {
+                // Commit the last straight leg and continue pulling from its end
+                smoothed.extend(best_leg.iter().skip(1));
+                anchor_index = index - 1;
+                best_leg = path[anchor_index..=index].to_vec();
+                index += 1;
+            }
+        }
+    }
+    smoothed.extend(best_leg.iter().skip(1));
+    smoothed
+}
+
+/// Point on the quadratic Bézier curve from `start` to `end` bent towards `control`.
+pub fn curve_point(start: Vec3, control: Vec3, end: Vec3, t: f32) -> Vec3 {
+    let inverse = 1.0 - t;
+    start * (inverse * inverse) + control * (2.0 * inverse * t) + end * (t * t)
+}
+
+/// Direction of travel along the curve at `t` (not normalized).
+pub fn curve_tangent(start: Vec3, control: Vec3, end: Vec3, t: f32) -> Vec3 {
+    (control - start) * (2.0 * (1.0 - t)) + (end - control) * (2.0 * t)
+}
+
+/// Approximate arc length of the curve: the average of the chord and the control polygon.
+pub fn curve_length(start: Vec3, control: Vec3, end: Vec3) -> f32 {
+    (start.distance(end) + start.distance(control) + control.distance(end)) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::movements::grid_map::GridMap;
+
+    fn staircase(length: i32) -> Vec<(i32, i32)> {
+        // Diagonal first, then straight: the typical shape of a grid search result
+        let mut path: Vec<_> = (0..=length / 2).map(|i| (i, i)).collect();
+        path.extend((length / 2 + 1..=length).map(|x| (x, length / 2)));
+        path
+    }
+
+    #[test]
+    fn straightens_staircase_on_open_ground() {
+        let map = GridMap::new(12, 12, 1.0);
+        let path = staircase(10);
+        let smoothed = smooth_path(&map, path.clone());
+        assert_eq!(smoothed, bresenham_line((0, 0), (10, 5)));
+        assert!(path_cost(&map, &smoothed).unwrap() <= path_cost(&map, &path).unwrap() + 0.001);
+    }
+
+    #[test]
+    fn keeps_corner_around_wall() {
+        let map = GridMap::from_ascii(
+            "
+            S....
+            ####.
+            G....
+            ",
+        );
+        let path = vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 1), (3, 2), (2, 2), (1, 2), (0, 2)];
+        let smoothed = smooth_path(&map, path);
+        assert!(smoothed.contains(&(4, 1)));
+        assert!(smoothed.iter().all(|&tile| map.is_walkable(tile)));
+        assert_eq!(smoothed.last(), map.marker('G').as_ref());
+    }
+
+    #[test]
+    fn never_cuts_through_costly_terrain() {
+        let map = GridMap::from_ascii(
+            "
+            ......
+            .&&&&.
+            .&&&&.
+            ......
+            ",
+        );
+        let path = vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (5, 0), (5, 1), (5, 2), (5, 3)];
+        let smoothed = smooth_path(&map, path.clone());
+        assert!(path_cost(&map, &smoothed).unwrap() <= path_cost(&map, &path).unwrap() + 0.001);
+        assert!(smoothed.iter().all(|&tile| map.movement_cost(tile) == 1.0));
+    }
+}
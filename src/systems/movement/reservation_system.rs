@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::components::movements::movement::Movement;
+use crate::components::movements::reservation::{RESERVATION_LOOKAHEAD, TileReservations};
+use crate::components::movements::tile_graph::TileGraph;
+use crate::components::{TileGrid, TilePosition};
+use crate::shared::CharacterType;
+
+/// Rebuilds occupancy from every character's `TilePosition` and re-announces the next
+/// few tiles of each path. Runs once per tick before anything moves.
+pub fn refresh_tile_reservations(
+    mut reservations: ResMut<TileReservations>,
+    characters: Query<(Entity, &TilePosition, Option<&Movement>), With<CharacterType>>,
+) {
+    reservations.holders.clear();
+    reservations.planned.clear();
+
+    for (entity, tile_position, _) in characters.iter() {
+        if let Some(tile) = tile_position.tile {
+            reservations.holders.insert(tile, entity);
+        }
+    }
+    for (entity, tile_position, movement) in characters.iter() {
+        let Some(movement) = movement else {
+            continue;
+        };
+        let upcoming = movement.path.iter().filter(|&&tile| Some(tile) != tile_position.tile);
+        for (step, &tile) in (1..=RESERVATION_LOOKAHEAD).zip(upcoming) {
+            reservations.plan(tile, entity, step);
+        }
+    }
+}
+
+/// Picks a free neighbour of `current` that still connects to `rejoin`, to walk around
+/// a character blocking the next tile. Both steps follow the searches' walkability and
+/// slope rules.
+pub fn find_sidestep(
+    character: Entity,
+    current: Entity,
+    blocked: Entity,
+    rejoin: Entity,
+    grid: &TileGrid,
+    reservations: &TileReservations,
+) -> Option<Entity> {
+    let current = grid.index_of_entity(current)?;
+    let rejoin_index = grid.index_of_entity(rejoin)?;
+    let rejoin_position = grid.position(rejoin_index);
+
+    TileGraph::neighbors(grid, current)
+        .filter_map(|candidate| Some((candidate, grid.entity(candidate)?)))
+        .filter(|&(_, entity)| entity != blocked)
+        .filter(|&(_, entity)| reservations.is_free_for(entity, character, 1))
+        .filter(|&(candidate, entity)| {
+            entity == rejoin || TileGraph::neighbors(grid, candidate).any(|n| n == rejoin_index)
+        })
+        .map(|(candidate, entity)| {
+            (entity, grid.position(candidate).distance_squared(rejoin_position))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(candidate, _)| candidate)
+}
+
+/// Closest free neighbour of an occupied `target`, measured from `from`, that a character
+/// could step onto `target` from.
+pub fn nearest_free_neighbor(
+    character: Entity,
+    target: Entity,
+    from: Vec3,
+    grid: &TileGrid,
+    reservations: &TileReservations,
+) -> Option<Entity> {
+    let target = grid.index_of_entity(target)?;
+    // Slopes are as steep either way, so the steps out of `target` are the steps into it
+    TileGraph::neighbors(grid, target)
+        .filter_map(|candidate| Some((candidate, grid.entity(candidate)?)))
+        .filter(|&(_, entity)| reservations.holder(entity).is_none_or(|h| h == character))
+        .map(|(candidate, entity)| (entity, grid.position(candidate).distance_squared(from)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(candidate, _)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three by three tiles in a single chunk; `(1, 2)` and `(2, 2)` are too high to step
+    /// onto from their neighbours
+    fn hill() -> TileGrid {
+        let mut grid = TileGrid::new(4, 1.0);
+        for z in 0..3 {
+            for x in 0..3 {
+                let height = if z == 2 && x > 0 { 2.0 } else { 0.0 };
+                let entity = Entity::from_raw_u32((z * 3 + x + 1) as u32).unwrap();
+                grid.insert((x, z), entity, Vec3::new(x as f32, height, z as f32), true, 1.0);
+            }
+        }
+        grid.link_neighbors();
+        grid
+    }
+
+    #[test]
+    fn sidesteps_only_onto_tiles_it_can_climb() {
+        let grid = hill();
+        let tile = |coord| grid.get(coord).unwrap();
+        let character = Entity::from_raw_u32(100).unwrap();
+        let mut reservations = TileReservations::default();
+        reservations.holders.insert(tile((1, 1)), Entity::from_raw_u32(101).unwrap());
+
+        // `(1, 2)` is as close to the rejoin tile as `(1, 0)`, but up a cliff
+        let sidestep =
+            find_sidestep(character, tile((0, 1)), tile((1, 1)), tile((2, 1)), &grid, &reservations);
+        assert_eq!(sidestep, Some(tile((1, 0))));
+    }
+
+    #[test]
+    fn stands_next_to_the_target_only_where_it_could_step_onto_it() {
+        let grid = hill();
+        let tile = |coord| grid.get(coord).unwrap();
+        let character = Entity::from_raw_u32(100).unwrap();
+        let mut reservations = TileReservations::default();
+        reservations.holders.insert(tile((1, 1)), Entity::from_raw_u32(101).unwrap());
+
+        // `(2, 2)` is the closest neighbour, but a cliff above the target
+        let from = Vec3::new(3.0, 0.0, 2.5);
+        let free = nearest_free_neighbor(character, tile((1, 1)), from, &grid, &reservations);
+        assert_eq!(free, Some(tile((2, 1))));
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::components::movements::steering::{Steering, SteeringConfig};
+use crate::components::{Tile, TilePosition};
+use crate::shared::CharacterType;
+
+/// Horizontal push away from `from`, fading linearly to zero at `radius`.
+fn repulsion(position: Vec3, from: Vec3, radius: f32, fallback_direction: Vec3) -> Vec3 {
+    let away = (position - from).with_y(0.0);
+    let distance = away.length();
+    if distance >= radius {
+        return Vec3::ZERO;
+    }
+    let direction = if distance > 0.001 { away / distance } else { fallback_direction };
+    direction * (1.0 - distance / radius)
+}
+
+/// Deterministic direction used to split characters standing on exactly the same spot.
+fn tie_break_direction(entity: Entity) -> Vec3 {
+    let angle = (entity.index() as f32) * 2.399_963; // golden angle spreads indices evenly
+    Vec3::new(angle.cos(), 0.0, angle.sin())
+}
+
+/// Separation and obstacle avoidance around the tile path.
+///
+/// Works on path anchors (position without the steering offset) so the offset never feeds
+/// back into path following. The offset eases towards its desired value, which gives
+/// characters a soft arrival instead of snapping when a neighbour moves away.
+pub fn update_steering(
+    config: Res<SteeringConfig>,
+    time: Res<Time>,
+    mut characters: Query<
+        (Entity, &mut Transform, Option<&mut Steering>, &TilePosition),
+        With<CharacterType>,
+    >,
+    tiles: Query<(&Transform, &Tile), Without<CharacterType>>,
+) {
+    let cell_size = config.separation_radius;
+    let cell_of = |position: Vec3| {
+        ((position.x / cell_size).floor() as i32, (position.z / cell_size).floor() as i32)
+    };
+
+    let mut anchors = HashMap::new();
+    let mut grid: HashMap<(i32, i32), Vec<(Entity, Vec3)>> = HashMap::new();
+    for (entity, transform, steering, _) in characters.iter() {
+        let anchor = transform.translation - steering.map_or(Vec3::ZERO, |s| s.offset);
+        anchors.insert(entity, anchor);
+        grid.entry(cell_of(anchor)).or_default().push((entity, anchor));
+    }
+
+    let blend = (config.responsiveness * time.delta_secs()).min(1.0);
+    for (entity, mut transform, steering, tile_position) in characters.iter_mut() {
+        let Some(mut steering) = steering else {
+            continue;
+        };
+        let anchor = anchors[&entity];
+
+        let mut desired = Vec3::ZERO;
+        let (cell_x, cell_z) = cell_of(anchor);
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                for &(other, other_anchor) in
+                    grid.get(&(cell_x + dx, cell_z + dz)).into_iter().flatten()
+                {
+                    if other != entity {
+                        desired += repulsion(
+                            anchor,
+                            other_anchor,
+                            config.separation_radius,
+                            tie_break_direction(entity),
+                        ) * config.separation_strength;
+                    }
+                }
+            }
+        }
+
+        if let Some((_, current_tile)) = tile_position.tile.and_then(|tile| tiles.get(tile).ok()) {
+            for &neighbor in current_tile.neighbor_entities.iter().flatten() {
+                if let Ok((neighbor_transform, neighbor_tile)) = tiles.get(neighbor)
+                    && !neighbor_tile.walkable
+                {
+                    desired += repulsion(
+                        anchor,
+                        neighbor_transform.translation,
+                        config.obstacle_radius,
+                        Vec3::ZERO,
+                    ) * config.obstacle_strength;
+                }
+            }
+        }
+
+        let desired = (desired * config.max_offset).clamp_length_max(config.max_offset);
+        let offset = steering.offset.lerp(desired, blend);
+        transform.translation += offset - steering.offset;
+        steering.offset = offset;
+    }
+}
@@ -0,0 +1,27 @@
+use bevy::prelude::*;
+
+use crate::components::TileRegistry;
+use crate::level::{Level, LevelAssets};
+use crate::shared::CharacterType;
+
+/// Keeps characters standing on the terrain.
+///
+/// Movement and steering only decide where a character is on the ground plane; its height
+/// is read back from the same blend of tile elevations the chunk meshes are built from, so
+/// feet stay on the surface between tile centres as well.
+pub fn follow_terrain(
+    tile_registry: Res<TileRegistry>,
+    level_assets: Res<LevelAssets>,
+    levels: Res<Assets<Level>>,
+    mut characters: Query<&mut Transform, With<CharacterType>>,
+) {
+    let Some(level) = level_assets.current(&levels) else {
+        return;
+    };
+    for mut transform in characters.iter_mut() {
+        let height = tile_registry.grid.height_at(level.tile_space(transform.translation));
+        if (transform.translation.y - height).abs() > f32::EPSILON {
+            transform.translation.y = height;
+        }
+    }
+}
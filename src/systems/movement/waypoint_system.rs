@@ -0,0 +1,160 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::components::movements::movement::{MoveRequestEvent, Movement, MovementType};
+use crate::components::movements::path_request::{
+    PathRequestQueue, PathTask, PathfindingConfig,
+};
+use crate::components::movements::waypoints::{Patrol, WaypointAction, WaypointEvent, Waypoints};
+use crate::components::{Tile, TilePosition};
+use crate::player::player::Player;
+
+const WAYPOINT_COLOR: Color = Color::srgb(1.0, 0.8, 0.0);
+
+type IdleCandidate = (Entity, &'static Movement, &'static TilePosition, Option<&'static PathTask>);
+
+/// Whether a character has no tiles left to walk and no search underway.
+fn between_moves(
+    entity: Entity,
+    movement: &Movement,
+    path_task: Option<&PathTask>,
+    path_request_queue: &PathRequestQueue,
+) -> bool {
+    movement.path.is_empty()
+        && path_task.is_none()
+        && path_request_queue.pending_target(entity).is_none()
+}
+
+pub fn handle_waypoint_events(
+    mut waypoint_events: MessageReader<WaypointEvent>,
+    mut waypoints: Query<&mut Waypoints>,
+) {
+    for event in waypoint_events.read() {
+        if let Ok(mut waypoints) = waypoints.get_mut(event.entity) {
+            waypoints.apply(event.action);
+            debug!("{} waypoints queued for {:?}", waypoints.queue.len(), event.entity);
+        } else {
+            warn!("Entity {:?} has no waypoint queue", event.entity);
+        }
+    }
+}
+
+/// Backspace drops the player's last queued waypoint, Escape drops them all.
+pub fn edit_waypoints_from_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    player_query: Query<(Entity, &Waypoints), With<Player>>,
+    mut waypoint_events: MessageWriter<WaypointEvent>,
+) {
+    let Ok((entity, waypoints)) = player_query.single() else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Escape) {
+        waypoint_events.write(WaypointEvent { entity, action: WaypointAction::Clear });
+    } else if keys.just_pressed(KeyCode::Backspace) && !waypoints.queue.is_empty() {
+        let last = waypoints.queue.len() - 1;
+        waypoint_events.write(WaypointEvent { entity, action: WaypointAction::Remove(last) });
+    }
+}
+
+/// Sends characters on to their next waypoint once they are out of tiles to walk.
+pub fn advance_waypoints(
+    mut characters: Query<(IdleCandidate, &mut Waypoints)>,
+    path_request_queue: Res<PathRequestQueue>,
+    config: Res<PathfindingConfig>,
+    mut move_request_writer: MessageWriter<MoveRequestEvent>,
+) {
+    for ((entity, movement, tile_position, path_task), mut waypoints) in characters.iter_mut() {
+        if !between_moves(entity, movement, path_task, &path_request_queue) {
+            continue;
+        }
+        let Some(current_tile) = tile_position.tile else {
+            continue;
+        };
+        // Waypoints on the tile already reached are done
+        while waypoints.queue.front() == Some(&current_tile) {
+            waypoints.queue.pop_front();
+        }
+        let Some(waypoint) = waypoints.queue.pop_front() else {
+            continue;
+        };
+
+        info!("Heading for the next waypoint, {} more queued", waypoints.queue.len());
+        move_request_writer.write(MoveRequestEvent {
+            movement_type: config.player_movement_type,
+            entity,
+            source_tile_entity: current_tile,
+            target_tile_entity: waypoint,
+        });
+    }
+}
+
+/// Walks patrolling characters from stop to stop, pausing once they stand still at each one.
+pub fn advance_patrols(
+    mut characters: Query<(IdleCandidate, &mut Patrol)>,
+    path_request_queue: Res<PathRequestQueue>,
+    mut move_request_writer: MessageWriter<MoveRequestEvent>,
+    time: Res<Time>,
+) {
+    for ((entity, movement, tile_position, path_task), mut patrol) in characters.iter_mut() {
+        if !between_moves(entity, movement, path_task, &path_request_queue)
+            || movement.target_transform.is_some()
+        {
+            continue;
+        }
+        let Some(current_tile) = tile_position.tile else {
+            continue;
+        };
+        patrol.waited += time.delta_secs();
+        if patrol.waited < patrol.pause {
+            continue;
+        }
+        patrol.waited = 0.0;
+
+        let Some(mut stop) = patrol.next_stop() else {
+            continue;
+        };
+        if stop == current_tile {
+            stop = patrol.next_stop().unwrap_or(stop);
+        }
+        move_request_writer.write(MoveRequestEvent {
+            movement_type: MovementType::HPASTAR,
+            entity,
+            source_tile_entity: current_tile,
+            target_tile_entity: stop,
+        });
+    }
+}
+
+/// Draws the pending waypoints, linked from the current destination onwards.
+pub fn draw_waypoints(
+    mut gizmos: Gizmos,
+    characters: Query<(&Waypoints, &Movement, &Transform)>,
+    tiles: Query<&Transform, With<Tile>>,
+) {
+    for (waypoints, movement, transform) in characters.iter() {
+        if waypoints.queue.is_empty() {
+            continue;
+        }
+        let start = movement
+            .path
+            .back()
+            .and_then(|&destination| tiles.get(destination).ok())
+            .map_or(transform.translation, |destination| destination.translation);
+        let stops: Vec<Vec3> = waypoints
+            .queue
+            .iter()
+            .filter_map(|&waypoint| tiles.get(waypoint).ok())
+            .map(|waypoint| waypoint.translation + Vec3::Y * 0.1)
+            .collect();
+
+        gizmos.linestrip(
+            std::iter::once(start + Vec3::Y * 0.1).chain(stops.iter().copied()),
+            WAYPOINT_COLOR,
+        );
+        for stop in stops {
+            let flat = Quat::from_rotation_x(FRAC_PI_2);
+            gizmos.circle(Isometry3d::new(stop, flat), 0.6, WAYPOINT_COLOR);
+        }
+    }
+}
@@ -1,32 +1,38 @@
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::collections::BTreeMap;
+
 use crate::{
-    components::{PlaneChunk, Tile, TileRegistry, TileSelectedEvent},
-    materials::pavement,
+    components::{
+        ChunkSurface, PlaneChunk, TerrainType, Tile, TileGrid, TileRegistry, TileSelectedEvent,
+        bilinear_height,
+    },
+    level::{Level, level_asset::LevelTile, tile_types::TileTypeRegistry},
 };
+use bevy::asset::RenderAssetUsages;
+use bevy::ecs::system::SystemParam;
+use bevy::mesh::{Indices, PrimitiveTopology};
 use bevy::prelude::*;
 use crate::player::player::Player;
+use crate::systems::chunk_streaming_system::StreamingContext;
+use crate::systems::tile_selection_system::TileClickInput;
 
-/// Spawns a grid of plane chunks arranged in columns and rows
+/// Spawns the chunk at `col`, `row` of the level along with its tiles
 ///
 /// # Arguments
-/// * `num_cols` - Number of columns in the grid
-/// * `num_rows` - Number of rows in the grid
-/// * `chunk_width` - Width of each individual chunk
-/// * `chunk_height` - Height of each individual chunk
-/// * `grid_size` - Number of tiles per chunk side
+/// * `level` - Level giving the chunk layout; the chunk may lie outside of it
+/// * `tile_types` - Material, walkability and cost of each tile type
+/// * `tile_at` - Terrain and walkability of each global tile coordinate
+/// * `(col, row)` - Column and row of the chunk in the grid
 pub fn spawn_single_chunk_grid(
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
-    pavement_materials: &mut ResMut<pavement::CheckedFloorMaterials>,
-    col: i32,
-    row: i32,
-    num_cols: i32,
-    num_rows: i32,
-    chunk_width: i32,
-    chunk_height: i32,
-    grid_size: i32,
+    tile_types: &TileTypeRegistry,
+    level: &Level,
+    tile_at: &impl Fn((i32, i32)) -> LevelTile,
+    (col, row): (i32, i32),
 ) {
+    let (num_cols, num_rows) = (level.chunk_cols, level.chunk_rows);
+    let (chunk_width, chunk_height) = (level.chunk_size(), level.chunk_size());
+    let grid_size = level.tiles_per_chunk;
     info!("Spawning optimized chunk grid: {}x{} chunks", num_cols, num_rows);
 
     // Calculate chunk position
@@ -48,26 +54,154 @@ pub fn spawn_single_chunk_grid(
 
     let transform = Transform::from_translation(Vec3::new(x_pos, 0.0, z_pos));
 
-    // ✅ SINGLE CLICKABLE MESH for the entire grid
-    commands.spawn((
-        Mesh3d(meshes.add(Plane3d::default().mesh().size(chunk_width as f32, chunk_height as f32))),
-        MeshMaterial3d(pavement_materials.material.clone()),
+    // ✅ ONE CLICKABLE MESH per tile type in the chunk
+    let mut chunk = commands.spawn((
         transform.clone(),
-        Pickable::default(),
+        Visibility::default(),
         plane_chunk,
         Name::new(format!("Grid Mesh ({}, {})", col, row)),
     ));
+    spawn_chunk_surfaces(&mut chunk, meshes, tile_types, &plane_chunk, tile_at);
 
     // ✅ STILL SPAWN INDIVIDUAL TILE ENTITIES for metadata
-    spawn_optimized_tile_entities(commands, &plane_chunk, &transform);
+    spawn_optimized_tile_entities(commands, &plane_chunk, &transform, tile_types, tile_at);
 
     debug!("Spawned optimized grid at position ({}, {})", x_pos, z_pos);
 }
 
+/// Redraws the ground of chunks whose `PlaneChunk` was marked changed after they spawned,
+/// which the level editor does when it repaints their tiles.
+pub fn refresh_chunk_surfaces(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_types: Res<TileTypeRegistry>,
+    context: StreamingContext,
+    chunk_query: Query<(Entity, Ref<PlaneChunk>, &Children)>,
+    surface_query: Query<(), With<ChunkSurface>>,
+) {
+    let Some(level) = context.level() else {
+        return;
+    };
+    for (chunk_entity, plane_chunk, children) in chunk_query.iter() {
+        if !plane_chunk.is_changed() || plane_chunk.is_added() {
+            continue;
+        }
+        for child in children.iter().filter(|&child| surface_query.contains(child)) {
+            commands.entity(child).despawn();
+        }
+        spawn_chunk_surfaces(
+            &mut commands.entity(chunk_entity),
+            &mut meshes,
+            &tile_types,
+            &plane_chunk,
+            &|coord| context.tile(level, coord),
+        );
+    }
+}
+
+/// Spawns the pickable ground of a chunk as its children, one mesh per tile type.
+fn spawn_chunk_surfaces(
+    chunk: &mut EntityCommands,
+    meshes: &mut ResMut<Assets<Mesh>>,
+    tile_types: &TileTypeRegistry,
+    plane_chunk: &PlaneChunk,
+    tile_at: &impl Fn((i32, i32)) -> LevelTile,
+) {
+    for (terrain, mesh) in chunk_surface_meshes(plane_chunk, tile_at) {
+        chunk.with_child((
+            ChunkSurface,
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(tile_types.get(terrain).material.clone()),
+            Pickable::default(),
+            Name::new(format!("{} ({}, {})", terrain, plane_chunk.x, plane_chunk.z)),
+        ));
+    }
+}
+
+/// Heightfield covering a chunk, split into one mesh per tile type.
+///
+/// There is a vertex on every tile centre, tile edge midpoint and tile corner. The surface
+/// passes exactly through each tile's elevation and blends towards its neighbours in between,
+/// the same way `TileGrid::height_at` does, so characters stay on the ground. Tiles just
+/// outside the chunk are sampled so neighbouring chunks meet. Normals are computed before
+/// the split, so the lighting shows no seams where tile types meet.
+fn chunk_surface_meshes(
+    plane_chunk: &PlaneChunk,
+    tile_at: &impl Fn((i32, i32)) -> LevelTile,
+) -> Vec<(TerrainType, Mesh)> {
+    let grid_size = plane_chunk.grid_size;
+    let (first_x, first_z) = (plane_chunk.x * grid_size, plane_chunk.z * grid_size);
+    // The chunk's tiles plus a one tile border, looked up once each
+    let side = grid_size + 2;
+    let tiles: Vec<LevelTile> = (-1..=grid_size)
+        .flat_map(|z| (-1..=grid_size).map(move |x| (first_x + x, first_z + z)))
+        .map(tile_at)
+        .collect();
+    let tile = |(x, z): (i32, i32)| {
+        let (x, z) = (x - first_x + 1, z - first_z + 1);
+        ((0..side).contains(&x) && (0..side).contains(&z))
+            .then(|| tiles[(z * side + x) as usize])
+    };
+
+    let steps = grid_size * 2;
+    let (width, depth) = (plane_chunk.width as f32, plane_chunk.height as f32);
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    for j in 0..=steps {
+        for i in 0..=steps {
+            let (u, v) = (i as f32 / steps as f32, j as f32 / steps as f32);
+            let point = Vec2::new(
+                first_x as f32 + i as f32 / 2.0 - 0.5,
+                first_z as f32 + j as f32 / 2.0 - 0.5,
+            );
+            let height = bilinear_height(|coord| tile(coord).map(|tile| tile.elevation), point);
+            positions.push([u * width - width / 2.0, height, v * depth - depth / 2.0]);
+            uvs.push([u, v]);
+        }
+    }
+    // Each tile is two by two quads
+    let row = (steps + 1) as u32;
+    let mut indices_by_terrain: BTreeMap<TerrainType, Vec<u32>> = BTreeMap::new();
+    for j in 0..steps {
+        for i in 0..steps {
+            let terrain = tile((first_x + i / 2, first_z + j / 2)).unwrap_or_default().terrain;
+            let corner = j as u32 * row + i as u32;
+            // Counter-clockwise seen from above
+            indices_by_terrain.entry(terrain).or_default().extend([
+                corner,
+                corner + row,
+                corner + 1,
+                corner + 1,
+                corner + row,
+                corner + row + 1,
+            ]);
+        }
+    }
+
+    let all_indices = indices_by_terrain.values().flatten().copied().collect();
+    let mut surface = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(all_indices));
+    surface.compute_smooth_normals();
+    indices_by_terrain
+        .into_iter()
+        .map(|(terrain, indices)| {
+            let mut mesh = surface.clone().with_inserted_indices(Indices::U32(indices));
+            if let Err(error) = mesh.generate_tangents() {
+                warn!("{} mesh without tangents, normal map disabled: {}", terrain, error);
+            }
+            (terrain, mesh)
+        })
+        .collect()
+}
+
 fn spawn_optimized_tile_entities(
     commands: &mut Commands,
     plane_chunk: &PlaneChunk,
     chunk_transform: &Transform,
+    tile_types: &TileTypeRegistry,
+    tile_at: &impl Fn((i32, i32)) -> LevelTile,
 ) {
     for local_z in 0..plane_chunk.grid_size {
         for local_x in 0..plane_chunk.grid_size {
@@ -83,20 +217,20 @@ fn spawn_optimized_tile_entities(
             let local_z_pos = (local_z as f32 * tile_height) - (plane_chunk.height as f32 / 2.0)
                 + (tile_height / 2.0);
 
-            let world_pos = chunk_transform.translation + Vec3::new(local_x_pos, 0.0, local_z_pos);
+            let level_tile = tile_at((global_x, global_z));
+            let world_pos = chunk_transform.translation
+                + Vec3::new(local_x_pos, level_tile.elevation, local_z_pos);
+            let tile_type = tile_types.get(level_tile.terrain);
 
             // Spawn tile entity WITHOUT mesh - just metadata
             commands.spawn((
                 Tile {
                     x: global_x, // Store GLOBAL coordinates
                     z: global_z, // Store GLOBAL coordinates
-                    walkable: true,
-                    selected: false,
-                    hovered: false,
-                    idle_color: Color::srgb(0.0, 0.0, 0.0),
-                    selected_color: Color::srgb(1.0, 0.0, 0.0),
-                    hovered_color: Color::srgb(0.0, 1.0, 0.0),
-                    neighbor_entities: [None; 8],
+                    walkable: level_tile.walkable && tile_type.walkable,
+                    terrain: level_tile.terrain,
+                    movement_cost: tile_type.movement_cost,
+                    ..default()
                 },
                 Transform::from_translation(world_pos), // ✅ Pass the grid's transform
                 Name::new(format!("Tile ({}, {})", global_x, global_z)),
@@ -105,67 +239,84 @@ fn spawn_optimized_tile_entities(
     }
 }
 
+/// Finds the tile under a pointer hit on a chunk's ground, the same way in play and in the
+/// level editor.
+#[derive(SystemParam)]
+pub struct TilePicker<'w, 's> {
+    surface_query: Query<'w, 's, &'static ChildOf, With<ChunkSurface>>,
+    chunk_query: Query<'w, 's, (&'static Transform, &'static PlaneChunk)>,
+    tile_registry: Res<'w, TileRegistry>,
+}
+
+impl TilePicker<'_, '_> {
+    /// Whether `entity` is part of a chunk's ground.
+    pub fn is_ground(&self, entity: Entity) -> bool {
+        self.surface_query.contains(entity)
+    }
+
+    /// Global coordinate and entity of the tile hit at `position` on `entity`, if `entity`
+    /// is a chunk's ground.
+    pub fn pick(&self, entity: Entity, position: Option<Vec3>) -> Option<((i32, i32), Entity)> {
+        // Hits land on a chunk's surfaces, the chunk itself has no mesh
+        let chunk_entity = self.surface_query.get(entity).map_or(entity, ChildOf::parent);
+        let (chunk_transform, plane_chunk) = self.chunk_query.get(chunk_entity).ok()?;
+        // The hit lies on the heightfield surface, so its x and z pick the tile however high
+        // the ground is
+        let (local_x, local_z) = world_to_tile_coords(position?, chunk_transform, plane_chunk)?;
+        let coord = (
+            plane_chunk.x * plane_chunk.grid_size + local_x,
+            plane_chunk.z * plane_chunk.grid_size + local_z,
+        );
+        // ✅ USE REGISTRY: O(1) lookup for the tile
+        Some((coord, self.tile_registry.grid.get(coord)?))
+    }
+}
+
 pub fn handle_optimized_grid_clicks(
     mut click_events: MessageReader<Pointer<Click>>,
     grid_query: Query<(&Transform, &PlaneChunk)>,
+    tile_picker: TilePicker,
     tile_registry: Res<TileRegistry>,
     mut tile_selected_events: MessageWriter<TileSelectedEvent>,
     player_query: Query<(&Transform, &Player)>,
+    mut click_input: TileClickInput,
 ) {
     for event in click_events.read() {
-        if let Ok((grid_transform, plane_chunk)) = grid_query.get(event.entity) {
-            if let Some(hit_position) = event.hit.position {
-                // Convert click position to tile coordinates
-                if let Some((local_x, local_z)) =
-                    world_to_tile_coords(hit_position, grid_transform, plane_chunk)
-                {
-                    // Calculate global coordinates for TARGET tile
-                    let target_global_x = plane_chunk.x * plane_chunk.grid_size + local_x;
-                    let target_global_z = plane_chunk.z * plane_chunk.grid_size + local_z;
-
-                    info!(
-                        "Looking for tile at global coordinates ({}, {})",
-                        target_global_x, target_global_z
-                    );
-
-                    // ✅ USE REGISTRY: O(1) lookup for target tile
-                    if let Some(&target_tile_entity) =
-                        tile_registry.tiles_by_coord.get(&(target_global_x, target_global_z))
-                    {
-                        if let Ok((player_transform, _)) = player_query.single() {
-                            // ✅ CORRECT: Use new function to find player's actual tile
-                            if let Some((source_global_x, source_global_z)) =
-                                find_player_tile_coords(player_transform, &grid_query)
-                            {
-                                info!(
-                                    "Looking for source tile at global coordinates ({}, {})",
-                                    source_global_x, source_global_z
-                                );
-
-                                // ✅ USE REGISTRY: O(1) lookup for source tile
-                                if let Some(&source_tile_entity) = tile_registry
-                                    .tiles_by_coord
-                                    .get(&(source_global_x, source_global_z))
-                                {
-                                    info!(
-                                        "Found source tile entity ({}, {})",
-                                        source_global_x, source_global_z
-                                    );
-
-                                    if source_tile_entity != target_tile_entity {
-                                        tile_selected_events.write(TileSelectedEvent {
-                                            source_tile_entity: source_tile_entity,
-                                            target_tile_entity: target_tile_entity,
-                                        });
-                                    }
-                                }
-                            }
-                        } else {
-                            info!("Player is not found?");
-                        }
-                    } else {
-                        info!("Something sus is happening");
-                    }
+        let Some(((target_global_x, target_global_z), target_tile_entity)) =
+            tile_picker.pick(event.entity, event.hit.position)
+        else {
+            continue;
+        };
+        info!("Clicked tile at global coordinates ({}, {})", target_global_x, target_global_z);
+
+        let run = click_input.wants_run(target_tile_entity);
+        let append = click_input.wants_append();
+        let Ok((player_transform, _)) = player_query.single() else {
+            info!("Player is not found?");
+            continue;
+        };
+        // ✅ CORRECT: Use new function to find player's actual tile
+        if let Some((source_global_x, source_global_z)) =
+            find_player_tile_coords(player_transform, &grid_query)
+        {
+            info!(
+                "Looking for source tile at global coordinates ({}, {})",
+                source_global_x, source_global_z
+            );
+
+            // ✅ USE REGISTRY: O(1) lookup for source tile
+            if let Some(source_tile_entity) =
+                tile_registry.grid.get((source_global_x, source_global_z))
+            {
+                info!("Found source tile entity ({}, {})", source_global_x, source_global_z);
+
+                if source_tile_entity != target_tile_entity {
+                    tile_selected_events.write(TileSelectedEvent {
+                        source_tile_entity: source_tile_entity,
+                        target_tile_entity: target_tile_entity,
+                        run,
+                        append,
+                    });
                 }
             }
         }
@@ -203,7 +354,8 @@ fn find_player_tile_coords(
     None
 }
 
-fn world_to_tile_coords(
+/// Chunk-local tile under a point on the chunk's ground, if it lies within the chunk.
+pub fn world_to_tile_coords(
     world_pos: Vec3,
     grid_transform: &Transform,
     plane_chunk: &PlaneChunk,
@@ -237,26 +389,34 @@ fn world_to_tile_coords(
 
 pub fn build_tile_registry(
     mut tile_registry: ResMut<TileRegistry>,
-    tile_query: Query<(Entity, &mut Tile)>,
+    mut tile_query: Query<(Entity, &mut Tile, &Transform)>,
+    chunk_query: Query<&PlaneChunk>,
 ) {
-    tile_registry.tiles_by_coord.clear();
-    for (entity, tile) in tile_query.iter() {
-        tile_registry.tiles_by_coord.insert((tile.x, tile.z), entity);
+    let (chunk_size, tile_size) = chunk_query
+        .iter()
+        .next()
+        .map_or((1, 1.0), |chunk| (chunk.grid_size, chunk.width as f32 / chunk.grid_size as f32));
+    let mut grid = TileGrid::new(chunk_size, tile_size);
+    for (entity, tile, transform) in tile_query.iter() {
+        grid.insert((tile.x, tile.z), entity, transform.translation, tile.walkable, tile.movement_cost);
     }
-    info!("Built TileRegistry with {} tiles", tile_registry.tiles_by_coord.len());
+    grid.link_neighbors();
+    info!("Built TileRegistry with {} tiles", grid.tile_count());
 
-    calculate_tile_neighbors(&tile_registry, tile_query);
+    calculate_tile_neighbors(&grid, &mut tile_query);
+    tile_registry.grid = grid;
 }
 
-fn calculate_tile_neighbors(
-    tile_registry: &ResMut<TileRegistry>,
-    mut tile_query: Query<(Entity, &mut Tile)>,
-) {
+/// Mirrors the grid's neighbour indices into `Tile.neighbor_entities`.
+fn calculate_tile_neighbors(grid: &TileGrid, tile_query: &mut Query<(Entity, &mut Tile, &Transform)>) {
     let mut updated_count = 0;
-    for (_, mut tile) in tile_query.iter_mut() {
-        let old_neighbors = tile.neighbor_entities;
-        tile.neighbor_entities = calculate_neighbors_for_tile(&tile_registry, tile.x, tile.z);
-        if old_neighbors != tile.neighbor_entities {
+    for (entity, mut tile, _) in tile_query.iter_mut() {
+        let Some(index) = grid.index_of_entity(entity) else {
+            continue;
+        };
+        let neighbors = grid.neighbors(index).map(|neighbor| grid.entity(neighbor));
+        if tile.neighbor_entities != neighbors {
+            tile.neighbor_entities = neighbors;
             updated_count += 1;
         }
     }
@@ -265,53 +425,6 @@ fn calculate_tile_neighbors(
     }
 }
 
-/// Calculate neighbor entities for a specific tile coordinate
-fn calculate_neighbors_for_tile(
-    tile_registry: &TileRegistry,
-    x: i32,
-    z: i32,
-) -> [Option<Entity>; 8] {
-    let directions = [
-        (0, 1),   // N
-        (1, 1),   // NE
-        (1, 0),   // E
-        (1, -1),  // SE
-        (0, -1),  // S
-        (-1, -1), // SW
-        (-1, 0),  // W
-        (-1, 1),  // NW
-    ];
-
-    let mut neighbors = [None; 8];
-
-    for (i, (dx, dz)) in directions.iter().enumerate() {
-        let neighbor_coord = (x + dx, z + dz);
-        if let Some(&neighbor_entity) = tile_registry.tiles_by_coord.get(&neighbor_coord) {
-            neighbors[i] = Some(neighbor_entity);
-        }
-    }
-
-    neighbors
-}
-pub fn get_middle<K,V>(map: &HashMap<K,V>) -> Option<(&K, &V)>
-where
-    K: Clone + Ord + Hash,
-    V: Clone
-{
-    if map.is_empty() {
-        return None;
-    }
-
-    let mut keys: Vec<&K> = map.keys().collect();
-    keys.sort();
-
-    let middle_index = keys.len() / 2;
-    if let Some(value) = map.get(keys[middle_index]) {
-        return Some((keys[middle_index], value));
-    }
-    None
-}
-
 pub fn calculate_tile_world_position(
     tile_coord: (i32, i32),
     grid_query: &Query<(&Transform, &PlaneChunk), Without<Player>>,
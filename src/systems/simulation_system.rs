@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::components::{SimulationTick, TransformInterpolation};
+
+/// Puts the simulated transform back before the fixed ticks run. A transform changed
+/// since it was rendered was moved outside the simulation (spawned, teleported) and
+/// becomes the new simulated state instead.
+pub fn restore_simulated_transforms(
+    mut query: Query<(&mut Transform, &mut TransformInterpolation)>,
+) {
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        match interpolation.current {
+            Some(current) if interpolation.rendered == Some(*transform) => {
+                transform.set_if_neq(current);
+            }
+            _ => {
+                interpolation.previous = Some(*transform);
+                interpolation.current = Some(*transform);
+            }
+        }
+    }
+}
+
+pub fn advance_simulation_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+/// Remembers where each entity was before this tick moves it.
+pub fn record_previous_transforms(mut query: Query<(&Transform, &mut TransformInterpolation)>) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.previous = Some(*transform);
+    }
+}
+
+/// Remembers where this tick left each entity.
+pub fn record_simulated_transforms(mut query: Query<(&Transform, &mut TransformInterpolation)>) {
+    for (transform, mut interpolation) in query.iter_mut() {
+        interpolation.current = Some(*transform);
+    }
+}
+
+/// Renders each entity between its last two simulated transforms, by how far real time
+/// has run ahead of the simulation.
+pub fn interpolate_transforms(
+    fixed_time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut TransformInterpolation)>,
+) {
+    let fraction = fixed_time.overstep_fraction();
+    for (mut transform, mut interpolation) in query.iter_mut() {
+        if let Some(blended) = interpolation.blend(fraction) {
+            transform.set_if_neq(blended);
+        }
+        interpolation.rendered = Some(*transform);
+    }
+}
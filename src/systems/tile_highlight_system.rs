@@ -0,0 +1,237 @@
+use std::collections::HashSet;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::light::NotShadowCaster;
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+
+use crate::components::movements::movement::PathNotFoundEvent;
+use crate::components::{
+    PlaneChunk, Tile, TileGrid, TileHighlight, TileOverlay, TilePosition, TileRegistry,
+    TileSelectedEvent,
+};
+use crate::player::player::Player;
+use crate::systems::plane_chunk_system::TilePicker;
+
+/// How far the overlay floats above the ground, so it never sinks into the surface
+const OVERLAY_LIFT: f32 = 0.05;
+
+/// Follows the pointer across chunk surfaces and records the tile under it.
+pub fn track_hovered_tile(
+    mut move_events: MessageReader<Pointer<Move>>,
+    mut out_events: MessageReader<Pointer<Out>>,
+    tile_picker: TilePicker,
+    mut highlight: ResMut<TileHighlight>,
+) {
+    let mut hovered = highlight.hovered;
+    // Moving from one surface to the next also leaves the first; the move comes after
+    if out_events.read().any(|event| tile_picker.is_ground(event.entity)) {
+        hovered = None;
+    }
+    for event in move_events.read() {
+        if tile_picker.is_ground(event.entity) {
+            hovered = tile_picker.pick(event.entity, event.hit.position).map(|(_, tile)| tile);
+        }
+    }
+    if highlight.hovered != hovered {
+        highlight.hovered = hovered;
+    }
+}
+
+/// Remembers the player's destination until it is reached or found unreachable.
+pub fn track_selected_tile(
+    mut tile_selected_events: MessageReader<TileSelectedEvent>,
+    mut path_not_found_events: MessageReader<PathNotFoundEvent>,
+    player_query: Query<(Entity, &TilePosition), With<Player>>,
+    mut highlight: ResMut<TileHighlight>,
+) {
+    let mut selected = highlight.selected;
+    if let Some(event) = tile_selected_events.read().last() {
+        selected = Some(event.target_tile_entity);
+    }
+    if let Ok((player, tile_position)) = player_query.single() {
+        let unreachable = path_not_found_events
+            .read()
+            .any(|event| event.entity == player && Some(event.target_tile_entity) == selected);
+        if unreachable || (selected.is_some() && tile_position.tile == selected) {
+            selected = None;
+        }
+    }
+    if highlight.selected != selected {
+        highlight.selected = selected;
+    }
+}
+
+/// Keeps `Tile.hovered` and `Tile.selected` in step with `TileHighlight`.
+pub fn sync_tile_flags(
+    highlight: Res<TileHighlight>,
+    mut previous: Local<TileHighlight>,
+    mut tile_query: Query<&mut Tile>,
+) {
+    if !highlight.is_changed() || *highlight == *previous {
+        return;
+    }
+    let mut set_flag = |entity: Option<Entity>, flag: fn(&mut Tile) -> &mut bool, on: bool| {
+        if let Some(mut tile) = entity.and_then(|entity| tile_query.get_mut(entity).ok()) {
+            *flag(&mut tile) = on;
+        }
+    };
+    if previous.hovered != highlight.hovered {
+        set_flag(previous.hovered, |tile| &mut tile.hovered, false);
+        set_flag(highlight.hovered, |tile| &mut tile.hovered, true);
+    }
+    if previous.selected != highlight.selected {
+        set_flag(previous.selected, |tile| &mut tile.selected, false);
+        set_flag(highlight.selected, |tile| &mut tile.selected, true);
+    }
+    *previous = highlight.clone();
+}
+
+/// Gives every newly spawned chunk its overlay. The overlay ignores the pointer so clicks
+/// and hovers reach the ground beneath it.
+pub fn spawn_tile_overlays(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut overlay_material: Local<Option<Handle<StandardMaterial>>>,
+    new_chunks: Query<(Entity, &PlaneChunk), Added<PlaneChunk>>,
+) {
+    for (chunk_entity, chunk) in new_chunks.iter() {
+        // Tints come from vertex colours
+        let material = overlay_material.get_or_insert_with(|| {
+            materials.add(StandardMaterial {
+                base_color: Color::WHITE,
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            })
+        });
+        commands.entity(chunk_entity).with_child((
+            TileOverlay,
+            MeshMaterial3d(material.clone()),
+            Transform::default(),
+            Visibility::default(),
+            NotShadowCaster,
+            NoFrustumCulling, // Bounds would go stale as the mesh comes and goes
+            Pickable::IGNORE,
+            Name::new(format!("Tile Overlay ({}, {})", chunk.x, chunk.z)),
+        ));
+    }
+}
+
+/// Rebuilds the overlay of every chunk whose tiles changed since the last frame.
+pub fn update_tile_overlays(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    tile_registry: Res<TileRegistry>,
+    changed_tiles: Query<&Tile, Changed<Tile>>,
+    tile_query: Query<&Tile>,
+    overlay_query: Query<(Entity, &ChildOf, Ref<TileOverlay>)>,
+    chunk_query: Query<&PlaneChunk>,
+) {
+    let grid = &tile_registry.grid;
+    let changed_chunks: HashSet<(i32, i32)> =
+        changed_tiles.iter().map(|tile| grid.chunk_of((tile.x, tile.z))).collect();
+    for (overlay, child_of, marker) in overlay_query.iter() {
+        let Ok(chunk) = chunk_query.get(child_of.parent()) else {
+            continue;
+        };
+        if !marker.is_added() && !changed_chunks.contains(&(chunk.x, chunk.z)) {
+            continue;
+        }
+        let tint = |coord| grid.get(coord).and_then(|entity| tile_query.get(entity).ok());
+        match overlay_mesh(chunk, grid, |coord| tint(coord).and_then(Tile::highlight_color)) {
+            Some(mesh) => commands.entity(overlay).insert(Mesh3d(meshes.add(mesh))),
+            None => commands.entity(overlay).remove::<Mesh3d>(),
+        };
+    }
+}
+
+/// Tinted tiles of a chunk as a mesh in the chunk's space, or `None` when no tile is tinted.
+///
+/// Each tile gets the same three by three vertices as the ground below it, lifted by
+/// `OVERLAY_LIFT`, so the overlay hugs slopes.
+fn overlay_mesh(
+    chunk: &PlaneChunk,
+    grid: &TileGrid,
+    tint_of: impl Fn((i32, i32)) -> Option<Color>,
+) -> Option<Mesh> {
+    let tile_size = chunk.width as f32 / chunk.grid_size as f32;
+    let (first_x, first_z) = (chunk.x * chunk.grid_size, chunk.z * chunk.grid_size);
+    let mut positions = Vec::new();
+    let mut colors = Vec::new();
+    let mut indices = Vec::new();
+    for z in first_z..first_z + chunk.grid_size {
+        for x in first_x..first_x + chunk.grid_size {
+            let Some(tint) = tint_of((x, z)) else {
+                continue;
+            };
+            let first = positions.len() as u32;
+            for j in 0..3 {
+                for i in 0..3 {
+                    let point =
+                        Vec2::new(x as f32 + i as f32 / 2.0 - 0.5, z as f32 + j as f32 / 2.0 - 0.5);
+                    positions.push([
+                        (point.x - first_x as f32 + 0.5) * tile_size - chunk.width as f32 / 2.0,
+                        grid.height_at(point) + OVERLAY_LIFT,
+                        (point.y - first_z as f32 + 0.5) * tile_size - chunk.height as f32 / 2.0,
+                    ]);
+                    colors.push(tint.to_linear().to_f32_array());
+                }
+            }
+            for j in 0..2 {
+                for i in 0..2 {
+                    let corner = first + j * 3 + i;
+                    // Counter-clockwise seen from above, like the ground
+                    indices.extend([corner, corner + 3, corner + 1]);
+                    indices.extend([corner + 1, corner + 3, corner + 4]);
+                }
+            }
+        }
+    }
+    if indices.is_empty() {
+        return None;
+    }
+    let normals = vec![[0.0, 1.0, 0.0]; positions.len()];
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices));
+    Some(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_covers_only_tinted_tiles() {
+        let chunk = PlaneChunk { x: 1, z: 0, width: 4, height: 4, grid_size: 2, ..default() };
+        let mut grid = TileGrid::new(2, 2.0);
+        for x in 0..4 {
+            for z in 0..2 {
+                let position = Vec3::new(x as f32, x as f32 * 0.5, z as f32);
+                grid.insert(
+                    (x, z),
+                    Entity::from_raw_u32(x as u32 * 2 + z as u32 + 1).unwrap(),
+                    position,
+                    true,
+                    1.0,
+                );
+            }
+        }
+
+        assert!(overlay_mesh(&chunk, &grid, |_| None).is_none());
+        let mesh =
+            overlay_mesh(&chunk, &grid, |coord| (coord == (3, 1)).then_some(Color::WHITE)).unwrap();
+        assert_eq!(mesh.count_vertices(), 9);
+        let Some(bevy::mesh::VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("overlay mesh has no positions");
+        };
+        // Centre vertex sits over the tile centre, on its elevation
+        assert_eq!(positions[4], [1.0, 1.5 + OVERLAY_LIFT, 1.0]);
+    }
+}
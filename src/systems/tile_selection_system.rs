@@ -1,7 +1,42 @@
 use crate::components::{Tile, TilePosition, TileSelectedEvent};
+use crate::components::movements::movement::PathNotFoundEvent;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use crate::player::player::Player;
 
+/// Two clicks on the same tile within this many seconds make a double-click
+const DOUBLE_CLICK_TIME: f32 = 0.35;
+
+/// Last clicked tile, to recognise double-clicks.
+#[derive(Default)]
+pub struct LastTileClick {
+    tile: Option<Entity>,
+    time: f32,
+}
+
+/// Modifiers of a tile click: Ctrl or a double-click runs, Shift queues a waypoint.
+#[derive(SystemParam)]
+pub struct TileClickInput<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    time: Res<'w, Time>,
+    last_click: Local<'s, LastTileClick>,
+}
+
+impl TileClickInput<'_, '_> {
+    /// Records a click on `tile` and returns whether it asks to run.
+    pub fn wants_run(&mut self, tile: Entity) -> bool {
+        let now = self.time.elapsed_secs();
+        let double_click = self.last_click.tile == Some(tile)
+            && now - self.last_click.time <= DOUBLE_CLICK_TIME;
+        *self.last_click = LastTileClick { tile: Some(tile), time: now };
+        double_click || self.keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    }
+
+    pub fn wants_append(&self) -> bool {
+        self.keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    }
+}
+
 /// Handles tile clicks and emits selection events
 pub fn handle_tile_selection(
     mut click_events: MessageReader<Pointer<Click>>,
@@ -9,6 +44,7 @@ pub fn handle_tile_selection(
     player_query: Query<Entity, (With<Player>, With<TilePosition>)>,
     tile_positions_query: Query<&TilePosition>,
     mut tile_selected_events: MessageWriter<TileSelectedEvent>,
+    mut click_input: TileClickInput,
 ) {
     if player_query.is_empty() {
         // No player present, ignore tile selections
@@ -19,6 +55,8 @@ pub fn handle_tile_selection(
         for event in click_events.read() {
             if let Ok(tile) = tile_query.get(event.entity) {
                 info!("Tile clicked at ({}, {}), sending event", tile.x, tile.z);
+                let run = click_input.wants_run(event.entity);
+                let append = click_input.wants_append();
 
                 if let Some(player_tile_entity) = player_tile_position.tile {
                     if player_tile_entity == event.entity {
@@ -30,15 +68,35 @@ pub fn handle_tile_selection(
                     tile_selected_events.write(TileSelectedEvent {
                         source_tile_entity: player_tile_entity,
                         target_tile_entity: event.entity,
+                        run,
+                        append,
                     });
                 } else {
                     // First time selecting a tile, set as player's current tile
                     tile_selected_events.write(TileSelectedEvent {
                         source_tile_entity: event.entity,
                         target_tile_entity: event.entity,
+                        run,
+                        append,
                     });
                 }
             }
         }
     }
 }
+
+/// Reports player destinations that turned out to be unreachable
+pub fn handle_unreachable_tile(
+    mut path_not_found_events: MessageReader<PathNotFoundEvent>,
+    player_query: Query<Entity, With<Player>>,
+    tile_query: Query<&Tile>,
+) {
+    for event in path_not_found_events.read() {
+        if !player_query.contains(event.entity) {
+            continue;
+        }
+        if let Ok(tile) = tile_query.get(event.target_tile_entity) {
+            info!("Tile ({}, {}) cannot be reached", tile.x, tile.z);
+        }
+    }
+}